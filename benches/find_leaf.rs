@@ -0,0 +1,46 @@
+//! Compares `find_leaf_only` with testing every entry, as `search_find`
+//! used to for a single component, with and without precomputed closures.
+//! Run with `cargo bench`.
+
+use std::time::Instant;
+
+use hanzi_search::ids::{IDSTable, IDS, Tag};
+
+const CHAI_DATA: &str = include_str!("../chai.txt");
+const NEEDLES: &[char] = &['口', '木', '氵', '龜', '鬱'];
+
+fn scan(table: &IDSTable, c: char) -> Vec<(char, Tag)> {
+    let mut result: Vec<(char, Tag)> = table.iter()
+        .filter(|(_, ids)| table.ids_has_subcomponent(ids, &IDS::Char(c)))
+        .map(|((k, t), _)| (k, t.clone()))
+        .collect();
+    result.sort();
+    result
+}
+
+fn main() {
+    let table = IDSTable::load_from_string(CHAI_DATA).unwrap();
+    let mut closed = table.clone();
+    let start = Instant::now();
+    closed.precompute_closures(16);
+    println!("precomputing closures: {:?}", start.elapsed());
+    for &c in NEEDLES {
+        let start = Instant::now();
+        let generic = scan(&table, c);
+        let generic_time = start.elapsed();
+
+        let start = Instant::now();
+        let cached = scan(&closed, c);
+        let cached_time = start.elapsed();
+        assert_eq!(generic, cached, "closures differ for {}", c);
+
+        let start = Instant::now();
+        let indexed = table.find_leaf_only(c);
+        let indexed_time = start.elapsed();
+
+        assert_eq!(generic, indexed, "results differ for {}", c);
+        println!("{}: {} results, generic {:?}, with closures {:?}, indexed {:?} ({:.0}x)",
+                 c, indexed.len(), generic_time, cached_time, indexed_time,
+                 generic_time.as_secs_f64() / indexed_time.as_secs_f64());
+    }
+}
@@ -0,0 +1,47 @@
+//! Measures the memory and key lookups of the full table, whose keys hold
+//! a tag id, against a map keyed by `(char, Tag)` with a tag per entry,
+//! as the table used to be. Run with `cargo bench --bench tag_pool`.
+
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::time::Instant;
+
+use hanzi_search::ids::{IDSTable, IDS, Tag};
+
+const CHAI_DATA: &str = include_str!("../chai.txt");
+
+// What a key's own copy of `tag` allocates
+fn tag_bytes(tag: &Tag) -> usize {
+    match tag {
+        Tag::Variant(regions) => regions.capacity() * size_of::<String>() + regions.iter().map(String::capacity).sum::<usize>(),
+        Tag::Anon(_) => 0,
+    }
+}
+
+fn main() {
+    let start = Instant::now();
+    let table = IDSTable::load_from_string(CHAI_DATA).unwrap();
+    println!("loading: {:?}", start.elapsed());
+    let keys: Vec<(char, Tag)> = table.iter().map(|((k, t), _)| (k, t.clone())).collect();
+
+    let per_entry: HashMap<(char, Tag), &IDS> = table.iter().map(|((k, t), ids)| ((k, t.clone()), ids)).collect();
+    // the tags the old keys held on top of the interned table, which keeps
+    // each distinct tag once
+    let key_bytes = per_entry.capacity() * (size_of::<(char, Tag)>() - size_of::<(char, u32)>())
+        + per_entry.keys().map(|(_, t)| tag_bytes(t)).sum::<usize>();
+    println!("{} entries: interned table {} KiB, tags per key would add {} KiB",
+             keys.len(), table.approximate_memory_bytes() / 1024, key_bytes / 1024);
+
+    let start = Instant::now();
+    let found = keys.iter().filter(|(k, t)| table.decomposition(*k, t).is_some()).count();
+    let interned_time = start.elapsed();
+    let start = Instant::now();
+    let found_per_entry = keys.iter().filter(|key| per_entry.contains_key(*key)).count();
+    let per_entry_time = start.elapsed();
+    assert_eq!(found, found_per_entry);
+    println!("looking up every key: interned {:?}, tag per key {:?}", interned_time, per_entry_time);
+
+    let start = Instant::now();
+    let primary = keys.iter().filter(|(k, _)| table.primary_decomposition(*k).is_some()).count();
+    println!("primary decomposition of every key: {:?} ({} found)", start.elapsed(), primary);
+}
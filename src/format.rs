@@ -0,0 +1,151 @@
+//! Text output of search results, used by the CLI and available to other
+//! front ends that print text. The WASM interface does not go through it:
+//! it hands the page structured values and leaves their display to it.
+//!
+//! Every formatter writes one result per line, except `JsonFormatter`,
+//! which writes a single array.
+
+use alloc::{format, string::{String, ToString}, vec::Vec};
+
+use crate::{ids::IDSTable, SearchMatch};
+
+pub trait ResultFormatter {
+    fn format(&self, matches: &[SearchMatch]) -> String;
+}
+
+/// `清 GTJ`, followed by a tab and `⿰氵青` with `show_ids`. With
+/// `codepoints` the character is written as `U+6E05`. Given the table
+/// the results come from, tags are labelled by `IDSTable::tag_for_display`.
+#[derive(Debug, Clone, Default)]
+pub struct PlainFormatter<'a> {
+    pub show_ids: bool,
+    pub codepoints: bool,
+    pub table: Option<&'a IDSTable>,
+}
+
+impl ResultFormatter for PlainFormatter<'_> {
+    fn format(&self, matches: &[SearchMatch]) -> String {
+        let mut out = String::new();
+        for m in matches {
+            let k = if self.codepoints { format!("U+{:04X}", m.character as u32) } else { m.character.to_string() };
+            let tag = match self.table {
+                Some(table) => table.tag_for_display(m.character, &m.tag),
+                None => m.tag.to_string(),
+            };
+            out.push_str(&format!("{} {}", k, tag));
+            if self.show_ids {
+                out.push_str(&format!("\t{}", m.ids));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// `[{"character":"清","tag":"GTJ","ids":"⿰氵青"}, ...]`
+#[derive(Debug, Clone, Default)]
+pub struct JsonFormatter;
+
+impl ResultFormatter for JsonFormatter {
+    fn format(&self, matches: &[SearchMatch]) -> String {
+        let objects: Vec<String> = matches.iter()
+            .map(|m| format!(
+                "{{\"character\":{},\"tag\":{},\"ids\":{}}}",
+                json_string(&m.character.to_string()), json_string(&m.tag.to_string()), json_string(&m.ids.to_string()),
+            ))
+            .collect();
+        format!("[{}]\n", objects.join(","))
+    }
+}
+
+/// The character, tag and decomposition separated by tabs, without a
+/// header line.
+#[derive(Debug, Clone, Default)]
+pub struct TsvFormatter;
+
+impl ResultFormatter for TsvFormatter {
+    fn format(&self, matches: &[SearchMatch]) -> String {
+        let mut out = String::new();
+        for m in matches {
+            out.push_str(&format!("{}\t{}\t{}\n", m.character, m.tag, m.ids));
+        }
+        out
+    }
+}
+
+/// The formats a front end can offer, e.g. with `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Plain,
+    Json,
+    Tsv,
+}
+
+impl core::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Format::Plain),
+            "json" => Ok(Format::Json),
+            "tsv" => Ok(Format::Tsv),
+            _ => Err(format!("Unknown format {}", s)),
+        }
+    }
+}
+
+/// `s` as a JSON string literal, quotes included.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use crate::ids::{parse, Tag};
+
+    fn matches() -> Vec<SearchMatch> {
+        vec![
+            SearchMatch { character: '清', tag: Tag::from("GTJ".to_string()), ids: parse("⿰氵青").unwrap() },
+            SearchMatch { character: '江', tag: Tag::Variant(vec![]), ids: parse("⿰氵工").unwrap() },
+        ]
+    }
+
+    #[test]
+    fn plain_formatter() {
+        assert_eq!(PlainFormatter::default().format(&matches()), "清 GTJ\n江 \n");
+        let formatter = PlainFormatter { show_ids: true, codepoints: true, table: None };
+        assert_eq!(formatter.format(&matches()), "U+6E05 GTJ\t⿰氵青\nU+6C5F \t⿰氵工\n");
+        assert_eq!(formatter.format(&[]), "");
+        let table = IDSTable::load_from_string("U+6C5F 江 ⿰氵工 ⿰氵工").unwrap();
+        let formatter = PlainFormatter { table: Some(&table), ..PlainFormatter::default() };
+        assert_eq!(formatter.format(&crate::decompose(&table, "江")), "江 variant 1\n江 variant 2\n");
+    }
+
+    #[test]
+    fn json_formatter() {
+        assert_eq!(JsonFormatter.format(&matches()),
+            "[{\"character\":\"清\",\"tag\":\"GTJ\",\"ids\":\"⿰氵青\"},{\"character\":\"江\",\"tag\":\"\",\"ids\":\"⿰氵工\"}]\n");
+        assert_eq!(JsonFormatter.format(&[]), "[]\n");
+        assert_eq!(json_string("a\"\\\n"), "\"a\\\"\\\\\\u000a\"");
+    }
+
+    #[test]
+    fn tsv_formatter() {
+        assert_eq!(TsvFormatter.format(&matches()), "清\tGTJ\t⿰氵青\n江\t\t⿰氵工\n");
+        assert_eq!("tsv".parse(), Ok(Format::Tsv));
+        assert!("xml".parse::<Format>().is_err());
+    }
+}
@@ -1,11 +1,39 @@
 #![allow(dead_code)]
 
-use std::{collections::HashMap, fs::File, io::{self, BufRead, BufReader}, path::Path};
+use alloc::{borrow::Cow, boxed::Box, collections::BTreeSet, format, string::{String, ToString}, sync::Arc, vec, vec::Vec};
+use core::{fmt, ops::{ControlFlow, Range}, str::FromStr};
+#[cfg(feature = "std")]
+use std::{fs::File, io::{self, BufRead, BufReader, BufWriter, Write}, path::Path};
 use nom::{
-    Finish, IResult, Parser, branch::alt, bytes::take_while1, character::satisfy, combinator::{eof, opt}, multi::many_m_n, sequence::delimited, character::complete::char,
+    Finish, IResult, Parser, branch::alt, bytes::complete::take_while1, character::complete::satisfy, combinator::{eof, opt}, multi::many_m_n, sequence::{delimited, preceded}, character::complete::char,
 };
 use log::{warn, debug};
 
+use crate::normalize::{fold_width, fold_width_char, normalize_char};
+
+/// Maps and sets used by the table: hashed with `std`, ordered otherwise.
+#[cfg(feature = "std")]
+pub type Map<K, V> = std::collections::HashMap<K, V>;
+#[cfg(feature = "std")]
+pub type Set<T> = std::collections::HashSet<T>;
+#[cfg(not(feature = "std"))]
+pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+#[cfg(not(feature = "std"))]
+pub type Set<T> = alloc::collections::BTreeSet<T>;
+
+/// The decomposition of an entry, which a table opened with `open_lazy`
+/// parses on first use; None if it does not parse. Shared between threads
+/// with `std`.
+#[cfg(feature = "std")]
+type Slot = std::sync::OnceLock<Option<IDS>>;
+#[cfg(not(feature = "std"))]
+type Slot = core::cell::OnceCell<Option<IDS>>;
+
+#[cfg(feature = "std")]
+type LoadError = io::Error;
+#[cfg(not(feature = "std"))]
+type LoadError = core::convert::Infallible;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct IDC(char);
 
@@ -13,10 +41,39 @@ pub struct IDC(char);
 pub enum Direction {
     Vert,
     Hort,
+    /// ⿴⿵⿶⿷⿸⿹⿺⿼⿽: the first component frames the second
+    Surround,
+    /// ⿻: the components are written over each other
+    Overlaid,
+    /// ⿾⿿㇯: the reflection and rotation of one component, and the
+    /// subtraction of the second component from the first
     Other,
 }
 
-const ENCODED_IDC: &str = "⿰⿱⿲⿳⿴⿵⿶⿷⿸⿹⿺⿻⿼⿽⿾⿿㇯";
+impl Direction {
+    /// Whether parts follow each other along a line, so that nested
+    /// compositions in this direction can be read as one list of parts.
+    pub fn is_linear(self) -> bool {
+        matches!(self, Direction::Hort | Direction::Vert)
+    }
+}
+
+/// Placed as the last child of a pattern composition, matches all remaining
+/// children of the haystack, whatever their number.
+pub const ELLIPSIS_CHAR: char = '…';
+
+// Passed to `ids_match` where nothing should act as a wildcard
+const NO_WILDCARD: char = '\0';
+
+// How many levels of decompositions to follow where data may be cyclic
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+// The IDCs of Unicode 15.1: U+2FF0–2FFB, the four added as U+2FFC–2FFF,
+// and ㇯ U+31EF, added with them but placed in the CJK Strokes block since
+// the IDC block was full
+fn is_idc(c: char) -> bool {
+    matches!(c, '\u{2FF0}'..='\u{2FFF}' | '\u{31EF}')
+}
 
 fn idc_arity(c: char) -> usize {
     match c {
@@ -26,19 +83,19 @@ fn idc_arity(c: char) -> usize {
     }
 }
 
-fn is_idc(c: char) -> bool {
-    ENCODED_IDC.contains(c)
-}
-
 impl IDC {
     pub fn new(idc: char) -> Option<IDC> {
-        if ENCODED_IDC.find(idc).is_some() {
-            return Some(IDC(idc));
+        if is_idc(idc) {
+            Some(IDC(idc))
         } else {
-            return None;
+            None
         }
     }
 
+    pub fn as_char(self) -> char {
+        self.0
+    }
+
     pub fn arity(self) -> usize {
         idc_arity(self.0)
     }
@@ -51,369 +108,3940 @@ impl IDC {
         }
     }
 
+    /// The left-to-right or top-to-bottom IDC taking `arity` components.
+    fn of_direction(dir: Direction, arity: usize) -> Option<IDC> {
+        match (dir, arity) {
+            (Direction::Hort, 2) => Some(IDC('⿰')),
+            (Direction::Hort, 3) => Some(IDC('⿲')),
+            (Direction::Vert, 2) => Some(IDC('⿱')),
+            (Direction::Vert, 3) => Some(IDC('⿳')),
+            _ => None,
+        }
+    }
+
     pub fn direction(self) -> Direction {
         match self.0 {
             '⿰' | '⿲' => Direction::Hort,
             '⿱' | '⿳' => Direction::Vert,
+            '⿴' | '⿵' | '⿶' | '⿷' | '⿸' | '⿹' | '⿺' | '⿼' | '⿽' => Direction::Surround,
+            '⿻' => Direction::Overlaid,
             _ => Direction::Other,
         }
     }
 
+    /// One of ⿴⿵⿶⿷⿸⿹⿺⿼⿽, where the first component frames the second.
+    pub fn is_surround(self) -> bool {
+        self.direction() == Direction::Surround
+    }
+
     pub fn is_same_direction(self, other: IDC) -> bool {
-        return self.direction() == other.direction()
+        self.direction() == other.direction()
+    }
+
+    /// Builds a composition of this IDC, checking that the number of
+    /// children matches its arity. Prefer this over constructing
+    /// `IDS::Composition` directly.
+    pub fn compose(self, children: Vec<IDS>) -> Result<IDS, IDSError> {
+        if children.len() != self.arity() {
+            return Err(IDSError::ArityMismatch {
+                idc: self,
+                expected: self.arity(),
+                found: children.len(),
+            });
+        }
+        Ok(IDS::Composition { idc: self, children })
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum IDS {
-    Char(char),
-    Special(String),
-    Composition {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IDSError {
+    ArityMismatch {
         idc: IDC,
-        children: Vec<IDS>,
-    }
+        expected: usize,
+        found: usize,
+    },
 }
 
-impl std::fmt::Display for IDS {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for IDSError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            IDS::Char(k) => write!(f, "{}", k),
-            IDS::Special(s) => write!(f, "{{{}}}", s),
-            IDS::Composition { idc, children } => {
-                write!(f, "{}", idc.0)?;
-                for c in children {
-                    write!(f, "{}", c)?;
-                }
-                Ok(())
-            }
+            IDSError::ArityMismatch { idc, expected, found } =>
+                write!(f, "{} takes {} components, got {}", idc.0, expected, found),
         }
     }
 }
 
+impl core::error::Error for IDSError {}
+
+/// Why a decomposition could not be parsed. Positions count characters
+/// from the start of the input.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct TaggedIDS {
-    pub ids: IDS,
-    pub tag: Tag,
+pub enum ParseError {
+    /// The input ended before the composition of `idc` at `position` got
+    /// all its components.
+    MissingComponents { idc: IDC, position: usize, expected: usize, found: usize },
+    /// A complete decomposition followed by more input at `position`.
+    TrailingInput { position: usize },
+    /// Nothing valid could be read at `position`.
+    Invalid { position: usize },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
-pub enum Tag {
-    Variant(String),
-    Anon(usize),
+impl ParseError {
+    fn shifted(self, offset: usize) -> ParseError {
+        match self {
+            ParseError::MissingComponents { idc, position, expected, found } =>
+                ParseError::MissingComponents { idc, position: position + offset, expected, found },
+            ParseError::TrailingInput { position } => ParseError::TrailingInput { position: position + offset },
+            ParseError::Invalid { position } => ParseError::Invalid { position: position + offset },
+        }
+    }
 }
 
-impl std::fmt::Display for Tag {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Tag::Variant(s) => write!(f, "{}", s),
-            Tag::Anon(_) => Ok(()),
+            ParseError::MissingComponents { idc, position, expected, found } =>
+                write!(f, "{} at position {} takes {} components, but the input ends after {}", idc.0, position, expected, found),
+            ParseError::TrailingInput { position } => write!(f, "unexpected input at position {}", position),
+            ParseError::Invalid { position } => write!(f, "invalid input at position {}", position),
         }
     }
 }
 
-impl From<String> for Tag {
-    fn from(val: String) -> Tag {
-        Tag::Variant(val)
+impl core::error::Error for ParseError {}
+
+/// Decompositions are ordered leaves first (characters, then specials),
+/// and compositions by IDC, then child by child.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IDS {
+    Char(char),
+    Special(String),
+    /// An entity reference such as `&CDP-8BF1;`, as used by CHISE data for
+    /// components without a code point.
+    Entity(String),
+    /// A character class such as `[氵冫]` in a pattern, matching any of its
+    /// members.
+    OneOf(Vec<char>),
+    /// A character followed by a variation selector, e.g. 葛 with U+E0100.
+    /// It matches its base character unless exact matching is enabled.
+    CharWithVS { base: char, vs: char },
+    /// A component marked as mirrored or rotated by a trailing modifier,
+    /// e.g. `止↔`. It matches its base unless exact matching is enabled.
+    Modified { base: Box<IDS>, modifier: char },
+    /// A component marked with the ideographic variation indicator U+303E,
+    /// e.g. `〾青`: some variant of its base that cannot be represented.
+    /// It only matches its base when variation indicators are lenient.
+    VariantOf { base: Box<IDS> },
+    Composition {
+        idc: IDC,
+        children: Vec<IDS>,
     }
 }
 
-#[derive(Default, Debug, Clone)]
-pub struct IDSTable {
-    table: HashMap<(char, Tag), IDS>,
-    tags: HashMap<char, Vec<Tag>>,
-}
+impl IDS {
+    /// A pattern for `inner` enclosed in `frame`, whichever side the frame
+    /// opens to. Use the wildcard as `inner` for anything in that frame; a
+    /// wildcard frame only stands for ⿴ frames, as in any other pattern.
+    pub fn enclosed_in(frame: char, inner: IDS) -> IDS {
+        IDS::Composition { idc: IDC('⿴'), children: vec![IDS::Char(frame), inner] }
+    }
 
-impl IDSTable {
-    pub fn load_file<P: AsRef<Path>>(path: P) -> io::Result<IDSTable> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut table: HashMap<(char, Tag), IDS> = HashMap::new();
-        let mut tags: HashMap<char, Vec<Tag>> = HashMap::new();
-        for (_i, line) in reader.lines().enumerate() {
-            let line = line.expect("valid line");
-            let parts = line.split_whitespace().collect::<Vec<_>>();
-            let Some(char) = parts[1].chars().next() else {
-                warn!("Malformed line {}", line);
-                continue;
-            };
-            for ids_str in parts.iter().skip(2) {
-                let Ok(tids) = parse_tagged(ids_str) else {
-                    warn!("Cannot parse IDS on line {}", line);
-                    continue;
-                };
-                let key = (char, tids.tag.clone());
-                if table.contains_key(&key) {
-                    let tag = Tag::Anon(tags.get(&char).unwrap().len());
-                    let key = (char, tag.clone());
-                    table.insert(key, tids.ids);
-                    tags.entry(char)
-                        .and_modify(|v| v.push(tag.clone()))
-                        .or_insert_with(|| vec![tag.clone()]);
-                } else {
-                    tags.entry(char).and_modify(|v| v.push(tids.tag.clone())).or_insert(vec![tids.tag.clone()]);
-                    table.insert(key, tids.ids);
-                }
-            }
-        }
-        Ok(IDSTable {
-            table,
-            tags,
-        })
+    /// The same tree with `f` applied to every character leaf (and class
+    /// member); other nodes are kept as they are.
+    pub fn map_chars<F: Fn(char) -> char>(&self, f: F) -> IDS {
+        self.map_chars_with(&f)
     }
 
-    pub fn load_from_string(content: &str) -> io::Result<IDSTable> {
-        let mut table = HashMap::new();
-        let mut tags: HashMap<char, Vec<Tag>> = HashMap::new();
-        for line in content.lines() {
-            let parts = line.split_whitespace().collect::<Vec<_>>();
-            if parts.len() < 3 {
-                continue;
-            }
-            let Some(char) = parts[1].chars().next() else {
-                continue;
-            };
-            for ids_str in parts.iter().skip(2) {
-                let Ok(tids) = parse_tagged(ids_str) else {
-                    warn!("Cannot parse IDS on line {}", line);
-                    continue;
-                };
-                let key = (char, tids.tag.clone());
-                if table.contains_key(&key) {
-                    let tag = Tag::Anon(tags.get(&char).unwrap().len());
-                    let key = (char, tag.clone());
-                    table.insert(key, tids.ids);
-                    tags.entry(char)
-                        .and_modify(|v| v.push(tag.clone()))
-                        .or_insert_with(|| vec![tag.clone()]);
-                } else {
-                    tags.entry(char).and_modify(|v| v.push(tids.tag.clone())).or_insert(vec![tids.tag.clone()]);
-                    table.insert(key, tids.ids);
-                }
-            }
+    fn map_chars_with(&self, f: &impl Fn(char) -> char) -> IDS {
+        match self {
+            IDS::Char(c) => IDS::Char(f(*c)),
+            IDS::OneOf(cs) => IDS::OneOf(cs.iter().map(|c| f(*c)).collect()),
+            IDS::CharWithVS { base, vs } => IDS::CharWithVS { base: f(*base), vs: *vs },
+            IDS::Modified { base, modifier } => IDS::Modified { base: Box::new(base.map_chars_with(f)), modifier: *modifier },
+            IDS::VariantOf { base } => IDS::VariantOf { base: Box::new(base.map_chars_with(f)) },
+            IDS::Composition { idc, children } => IDS::Composition {
+                idc: *idc,
+                children: children.iter().map(|c| c.map_chars_with(f)).collect(),
+            },
+            other => other.clone(),
         }
-        Ok(IDSTable { table, tags })
     }
 
-    pub fn ids_match(&self, a: &IDS, b: &IDS, wildcard_k: char) -> bool {
-        use IDS::*;
-        match (a, b) {
-            (Char(a), _) if a == &wildcard_k => true,
-            (_, Char(b)) if b == &wildcard_k => true,
-            (Special(a), Special(b)) => a == b,
-            (Char(a), Char(b)) => a == b,
-            (Char(k), Composition { .. }) => {
-                let Some(k_tags) = self.tags.get(k) else {
-                    return false;
-                };
-                for k_tag in k_tags {
-                    if let Some(k_components) = self.table.get(&(*k, k_tag.clone())) {
-                        if k_components != &IDS::Char(*k) {
-                            if self.ids_match(k_components, b, wildcard_k) {
-                                return true;
-                            }
-                        }
-                    }
-                }
-                false
-            }
-            (Composition { .. }, Char(_)) => {
-                return self.ids_match(b, a, wildcard_k);
-            }
-            (x @ Composition { idc: xc, children: xs, .. }, y @ Composition { idc: yc, children: ys, .. }) => {
-                if xc == yc {
-                    for (x, y) in xs.iter().zip(ys.iter())  {
-                        if !self.ids_match(x, y, wildcard_k) {
-                            return false;
-                        }
-                    }
-                    return true;
-                } else if xc.arity() == 3 && yc.arity() == 2 && xc.is_same_direction(*yc) {
-                    // try to match ⿳abc with ⿱de
-                    let a = xs[0].clone();
-                    let b = xs[1].clone();
-                    let c = xs[2].clone();
-                    let d = ys[0].clone();
-                    let e = ys[1].clone();
-                    let ab = Composition { idc: xc.reduce().unwrap(), children: vec![a.clone(), b.clone()] };
-                    let bc = Composition { idc: xc.reduce().unwrap(), children: vec![b.clone(), c.clone()] };
-                    return (self.ids_match(&ab, &d, wildcard_k) && self.ids_match(&c, &e, wildcard_k)) ||
-                        (self.ids_match(&a, &d, wildcard_k) && self.ids_match(&bc, &e, wildcard_k));
-                } else if xc.arity() == 2 && yc.arity() == 3 {
-                    return self.ids_match(y, x, wildcard_k);
-                }
-                false
-            }
-            _ => false,
+    /// The same tree with `f` applied to the contents of every `Special`.
+    pub fn map_specials<F: Fn(&str) -> String>(&self, f: F) -> IDS {
+        self.map_specials_with(&f)
+    }
+
+    fn map_specials_with(&self, f: &impl Fn(&str) -> String) -> IDS {
+        match self {
+            IDS::Special(s) => IDS::Special(f(s)),
+            IDS::Modified { base, modifier } => IDS::Modified { base: Box::new(base.map_specials_with(f)), modifier: *modifier },
+            IDS::VariantOf { base } => IDS::VariantOf { base: Box::new(base.map_specials_with(f)) },
+            IDS::Composition { idc, children } => IDS::Composition {
+                idc: *idc,
+                children: children.iter().map(|c| c.map_specials_with(f)).collect(),
+            },
+            other => other.clone(),
         }
     }
 
-    pub fn ids_has_matching_subcomponent(&self, a: &IDS, b: &IDS, wildcard_k: char) -> bool {
-        use IDS::*;
-        if self.ids_match(a, b, wildcard_k) {
-            return true;
+    /// The same tree with every subtree equal to `target` replaced by
+    /// `replacement`.
+    pub fn replace(&self, target: &IDS, replacement: &IDS) -> IDS {
+        self.replace_counting(target, replacement, &mut 0)
+    }
+
+    fn replace_counting(&self, target: &IDS, replacement: &IDS, count: &mut usize) -> IDS {
+        if self == target {
+            *count += 1;
+            return replacement.clone();
         }
-        match (a, b) {
-            (Char(a), _) if a == &wildcard_k => true,
-            (_, Char(b)) if b == &wildcard_k => true,
-            (Special(a), Special(b)) => a == b,
-            (Special(_), _) => false,
-            (Char(ka), _) => {
-                if let Char(kb) = b {
-                    if ka == kb {
-                        return true;
-                    }
-                }
-                for asub in self.expand_char(*ka) {
-                    if self.ids_has_matching_subcomponent(&asub.ids, b, wildcard_k) {
-                        return true;
-                    }
-                }
-                false
+        match self {
+            IDS::Composition { idc, children } => IDS::Composition {
+                idc: *idc,
+                children: children.iter().map(|c| c.replace_counting(target, replacement, count)).collect(),
             },
-            (Composition { children: xs, .. }, b) => {
-                for x in xs {
-                    if self.ids_has_matching_subcomponent(x, b, wildcard_k) {
-                        return true;
-                    }
-                }
-                false
-            }
+            IDS::Modified { base, modifier } => IDS::Modified { base: Box::new(base.replace_counting(target, replacement, count)), modifier: *modifier },
+            IDS::VariantOf { base } => IDS::VariantOf { base: Box::new(base.replace_counting(target, replacement, count)) },
+            other => other.clone(),
         }
     }
 
-    fn expand_char(&self, k: char) -> Vec<TaggedIDS> {
-        let Some(tags) = self.tags.get(&k) else {
-            return vec![];
-        };
-        let mut result = vec![];
-        for tag in tags {
-            if let Some(components) = self.table.get(&(k, tag.clone())) {
-                if components != &IDS::Char(k) {
-                    result.push(TaggedIDS {
-                        tag: tag.clone(),
-                        ids: components.clone(),
-                    });
-                }
+    /// Every `Char` leaf of the tree, in reading order. Characters with a
+    /// variation selector count as their base character.
+    pub fn leaves(&self) -> Vec<char> {
+        self.iter_nodes()
+            .filter_map(|node| match node {
+                IDS::Char(k) | IDS::CharWithVS { base: k, .. } => Some(*k),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // `leaves`, leaving out those under a `〾`
+    fn plain_leaves(&self) -> Vec<char> {
+        let mut leaves = vec![];
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            match node {
+                IDS::Char(k) | IDS::CharWithVS { base: k, .. } => leaves.push(*k),
+                IDS::Composition { children, .. } => stack.extend(children.iter().rev()),
+                IDS::Modified { base, .. } => stack.push(base),
+                _ => {}
             }
         }
-        result
+        leaves
     }
 
-    pub fn ids_has_subcomponent(&self, haystack: &IDS, needle: &IDS) -> bool {
-        debug!("has_subcomponent haystack={:?} needle={:?}", haystack, needle);
-        if haystack == needle {
-            return true;
-        }
-        use IDS::*;
-        match (haystack, needle) {
-            (Special(a), Special(b)) => a == b,
-            (Special(_), _) => false,
-            (Char(a), Char(b)) if a == b => true,
-            (Char(a), Char(b)) if a != b && !self.tags.contains_key(a) => false,
-            (Char(a), _) => {
-                let Some(tags) = self.tags.get(a) else {
-                    return false;
-                };
-                for tag in tags {
-                    if let Some(a_components) = self.table.get(&(*a, tag.clone())) {
-                        if a_components != &IDS::Char(*a) {
-                            if self.ids_has_subcomponent(a_components, needle) {
-                                return true;
-                            }
-                        }
-                    }
-                }
-                false
-            },
-            (Composition { children, .. }, _) => {
-                for c in children {
-                    if self.ids_has_subcomponent(c, needle) {
-                        return true;
-                    }
-                }
-                false
-            }
+    /// Every `Special` placeholder of the tree, in reading order.
+    pub fn specials(&self) -> Vec<&str> {
+        self.iter_nodes()
+            .filter_map(|node| match node {
+                IDS::Special(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Every node of the tree in pre-order: a composition comes before its
+    /// children, which come in reading order. Deep trees do not use up the
+    /// call stack.
+    pub fn iter_nodes(&self) -> Nodes<'_> {
+        Nodes { stack: vec![self] }
+    }
+
+    /// Number of leaves of the tree as written, without expanding any.
+    pub fn leaf_count(&self) -> usize {
+        match self {
+            IDS::Composition { children, .. } => children.iter().map(IDS::leaf_count).sum(),
+            IDS::Modified { base, .. } | IDS::VariantOf { base } => base.leaf_count(),
+            _ => 1,
         }
     }
 
-    pub fn char_has_subcomponent(&self, k: char, needle: &IDS) -> bool {
-        let ids = IDS::Char(k);
-        self.ids_has_subcomponent(&ids, needle)
+    /// Number of nodes of the tree as written, leaves included.
+    pub fn node_count(&self) -> usize {
+        match self {
+            IDS::Composition { children, .. } => 1 + children.iter().map(IDS::node_count).sum::<usize>(),
+            IDS::Modified { base, .. } | IDS::VariantOf { base } => base.node_count(),
+            _ => 1,
+        }
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (&(char, Tag), &IDS)> {
-        self.table.iter()
+    /// Height of the tree: 0 for a leaf, 1 for a composition of leaves, etc.
+    pub fn depth(&self) -> usize {
+        match self {
+            IDS::Composition { children, .. } => 1 + children.iter().map(IDS::depth).max().unwrap_or(0),
+            IDS::Modified { base, .. } | IDS::VariantOf { base } => base.depth(),
+            _ => 0,
+        }
     }
-}
 
-fn parser_tag(input: &str) -> IResult<&str, String> {
-    delimited(
-        char('['),
-        take_while1(|c| c != ']'),
-        char(']'),
-    )
-        .map(String::from)
-        .parse(input)
-}
+    /// The byte range that the subtree at the child-index `path` occupies in
+    /// the `Display` output of this IDS.
+    pub fn display_span(&self, path: &[usize]) -> Option<Range<usize>> {
+        let Some((&first, rest)) = path.split_first() else {
+            return Some(0..self.to_string().len());
+        };
+        if let IDS::Modified { base, .. } = self {
+            return base.display_span(path);
+        }
+        if let IDS::VariantOf { base } = self {
+            let offset = VARIATION_INDICATOR.len_utf8();
+            return base.display_span(path).map(|span| span.start + offset..span.end + offset);
+        }
+        let IDS::Composition { idc, children } = self else {
+            return None;
+        };
+        let mut offset = idc.0.len_utf8();
+        for c in children.iter().take(first) {
+            offset += c.to_string().len();
+        }
+        let span = children.get(first)?.display_span(rest)?;
+        Some(span.start + offset..span.end + offset)
+    }
 
-fn parser_special(input: &str) -> IResult<&str, IDS> {
-    delimited(
-        satisfy(|c| c == '{'),
-        take_while1(|c| c != '}'),
-        satisfy(|c| c == '}')
-    )
-        .map(|s: &str| IDS::Special(s.to_string()))
-        .parse(input)
 }
 
-fn parser_char(input: &str) -> IResult<&str, IDS> {
-    satisfy(|c| !is_idc(c) && !"{[".contains(c))
-        .map(IDS::Char)
-        .parse(input)
+/// Iterator returned by `IDS::iter_nodes`.
+#[derive(Debug, Clone)]
+pub struct Nodes<'a> {
+    stack: Vec<&'a IDS>,
 }
 
-fn parser_composition(input: &str) -> IResult<&str, IDS> {
-    let (input, idc_char) = satisfy(is_idc).parse(input)?;
-    let arity = idc_arity(idc_char);
-    let (input, children) = many_m_n(arity, arity, parser_ids).parse(input)?;
-    Ok((input, IDS::Composition {
-        idc: IDC::new(idc_char).unwrap(),
-        children,
-    }))
-}
+impl<'a> Iterator for Nodes<'a> {
+    type Item = &'a IDS;
 
-fn parser_ids(input: &str) -> IResult<&str, IDS> {
-    alt((parser_composition, parser_special, parser_char)).parse(input)
+    fn next(&mut self) -> Option<&'a IDS> {
+        let node = self.stack.pop()?;
+        match node {
+            IDS::Composition { children, .. } => self.stack.extend(children.iter().rev()),
+            IDS::Modified { base, .. } | IDS::VariantOf { base } => self.stack.push(base),
+            _ => {}
+        }
+        Some(node)
+    }
 }
 
-fn parser_tagged_ids(input: &str) -> IResult<&str, TaggedIDS> {
-    (parser_ids, opt(parser_tag), eof)
-        .map(|(ids, tag, _)| TaggedIDS {
-            ids,
-            tag: Tag::Variant(tag.unwrap_or_default()),
-        })
-        .parse(input)
-}
+impl FromStr for IDS {
+    type Err = ParseError;
 
-pub fn parse(input: &str) -> Result<IDS, String> {
-    match parser_ids(input).finish() {
-        Ok((input, ids)) if input.is_empty() => Ok(ids),
-        Ok(_) => Err("Input is not parsed completely".to_string()),
-        Err(e) => Err(e.to_string())
+    /// Same as `parse`.
+    fn from_str(s: &str) -> Result<IDS, ParseError> {
+        parse(s)
     }
 }
 
-pub fn parse_tagged(input: &str) -> Result<TaggedIDS, String> {
-    match parser_tagged_ids(input).finish() {
-        Ok((input, tids)) if input.is_empty() => Ok(tids),
-        Ok(_) => Err("Input is not parsed completely".to_string()),
-        Err(e) => Err(e.to_string())
+impl fmt::Display for IDS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IDS::Char(k) => write!(f, "{}", k),
+            IDS::Special(s) => write!(f, "{{{}}}", s),
+            IDS::Entity(s) => write!(f, "&{};", s),
+            IDS::CharWithVS { base, vs } => write!(f, "{}{}", base, vs),
+            IDS::Modified { base, modifier } => write!(f, "{}{}", base, modifier),
+            IDS::VariantOf { base } => write!(f, "{}{}", VARIATION_INDICATOR, base),
+            IDS::OneOf(cs) => {
+                write!(f, "[")?;
+                for c in cs {
+                    write!(f, "{}", c)?;
+                }
+                write!(f, "]")
+            }
+            IDS::Composition { idc, children } => {
+                write!(f, "{}", idc.0)?;
+                for c in children {
+                    write!(f, "{}", c)?;
+                }
+                Ok(())
+            }
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// How a child of a `MixedPattern` has to match the part in its place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChildPattern {
+    /// The part is this decomposition, as in `match`.
+    Exact(IDS),
+    /// Any part at all.
+    Any,
+    /// The part contains this decomposition somewhere, as in `pmatch`.
+    Contains(IDS),
+}
+
+/// A composition whose children each match in their own way, parsed by
+/// `parse_mixed`. Left-out trailing children match anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixedPattern {
+    pub idc: IDC,
+    pub children: Vec<ChildPattern>,
+}
+
+/// Put before a child of a mixed pattern that only has to be contained in
+/// its part, as in `⿰女*子`.
+pub const CONTAINS_MARKER: char = '*';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaggedIDS {
+    pub ids: IDS,
+    pub tag: Tag,
+}
+
+/// The source tag of a decomposition. Named variants carry the region
+/// codes listed between the brackets, e.g. `[GTKV]` or `[G,T,UCS2003]`, in
+/// their original order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum Tag {
+    Variant(Vec<String>),
+    Anon(usize),
+}
+
+impl Tag {
+    pub fn regions(&self) -> &[String] {
+        match self {
+            Tag::Variant(regions) => regions,
+            Tag::Anon(_) => &[],
+        }
+    }
+
+    /// No region tag: either written without one or numbered on load.
+    pub fn is_untagged(&self) -> bool {
+        self.regions().is_empty()
+    }
+
+    pub fn has_region(&self, region: &str) -> bool {
+        self.regions().iter().any(|r| r == region)
+    }
+
+    /// The kind of decomposition the tag marks with `A` or `S`, if any.
+    pub fn kind(&self) -> Option<DecompositionKind> {
+        [DecompositionKind::Apparent, DecompositionKind::Structural].into_iter()
+            .find(|kind| self.has_region(kind.marker()))
+    }
+
+    /// The same tag with fullwidth letters in its region codes folded to
+    /// halfwidth.
+    pub fn fold_width(&self) -> Tag {
+        match self {
+            Tag::Variant(regions) => Tag::Variant(regions.iter().map(|r| fold_width(r)).collect()),
+            Tag::Anon(n) => Tag::Anon(*n),
+        }
+    }
+}
+
+/// Whether a decomposition describes how a character is written (`[A]`,
+/// apparent) or how it is built etymologically (`[S]`, structural), for
+/// data that gives both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecompositionKind {
+    Apparent,
+    Structural,
+}
+
+impl DecompositionKind {
+    /// The code marking the kind in a tag
+    pub fn marker(self) -> &'static str {
+        match self {
+            DecompositionKind::Apparent => "A",
+            DecompositionKind::Structural => "S",
+        }
+    }
+}
+
+// Letters that are region codes of their own, written run together as in
+// `[GTJ]`: the IRG sources and the A/S markers of the decomposition kind
+const REGION_LETTERS: &str = "AGHJKMPSTUVX";
+
+fn is_region_letter(c: char) -> bool {
+    REGION_LETTERS.contains(fold_width_char(c))
+}
+
+/// Splits the contents of a tag into region codes: comma-separated codes
+/// may be several letters long, and so may a code without commas, such as
+/// `UCS2003`, unless it is made only of region letters like `GTJ`, which
+/// are one code each.
+fn parse_regions(s: &str) -> Vec<String> {
+    let codes: Vec<String> = if s.contains(',') {
+        s.split(',').map(|r| r.trim().to_string()).filter(|r| !r.is_empty()).collect()
+    } else if s.chars().all(is_region_letter) {
+        s.chars().map(String::from).collect()
+    } else {
+        vec![s.trim().to_string()]
+    };
+    let mut regions = vec![];
+    for code in codes {
+        if !regions.contains(&code) {
+            regions.push(code);
+        }
+    }
+    regions
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // only single region letters are read back one by one
+            Tag::Variant(regions) if regions.iter().all(|r| r.chars().count() == 1 && r.chars().all(is_region_letter)) => {
+                write!(f, "{}", regions.concat())
+            }
+            // a lone code of region letters such as `KP` would be read back
+            // letter by letter without its comma
+            Tag::Variant(regions) if regions.len() == 1 && regions[0].chars().all(is_region_letter) => write!(f, "{},", regions[0]),
+            Tag::Variant(regions) => write!(f, "{}", regions.join(",")),
+            Tag::Anon(i) => write!(f, "~{}", i),
+        }
+    }
+}
+
+impl From<String> for Tag {
+    fn from(val: String) -> Tag {
+        Tag::Variant(parse_regions(&val))
+    }
+}
+
+/// Reads a tag as `Display` writes it: region codes such as `GTJ` or
+/// `G,T,UCS2003`, or `~1` for an anonymous variant. The brackets of data
+/// files are not part of it.
+impl FromStr for Tag {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Tag, ParseError> {
+        if let Some(position) = s.chars().position(|c| "[]".contains(c)) {
+            return Err(ParseError::Invalid { position });
+        }
+        match s.strip_prefix('~') {
+            Some(n) => n.parse().map(Tag::Anon).map_err(|_| ParseError::Invalid { position: 1 }),
+            None => Ok(Tag::Variant(parse_regions(s))),
+        }
+    }
+}
+
+/// Where the fields of a data line are. Columns count from 0; every
+/// column from `ids_start_column` on, other than those of the character
+/// and code point, holds a decomposition. The default is the layout of
+/// `U+6E05 清 ⿰氵青`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadOptions {
+    pub char_column: usize,
+    pub ids_start_column: usize,
+    /// The column checked against the character, if there is one
+    pub codepoint_column: Option<usize>,
+    /// The character between fields; None splits at runs of whitespace
+    pub delimiter: Option<char>,
+    /// If set, decompositions tagged only with other regions are skipped.
+    /// Untagged ones, and those only marked `A` or `S`, are always kept.
+    pub regions: Option<Vec<String>>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions { char_column: 1, ids_start_column: 2, codepoint_column: Some(0), delimiter: None, regions: None }
+    }
+}
+
+impl LoadOptions {
+    fn keeps(&self, tag: &Tag) -> bool {
+        let Some(allowed) = &self.regions else {
+            return true;
+        };
+        let markers = [DecompositionKind::Apparent.marker(), DecompositionKind::Structural.marker()];
+        let mut regions = tag.regions().iter().filter(|r| !markers.contains(&r.as_str())).peekable();
+        regions.peek().is_none() || regions.any(|r| allowed.contains(r))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    Malformed { line: usize },
+    ParseFailure { line: usize, ids: String },
+    DuplicateKey { line: usize, character: char, tag: Tag },
+    UnknownComponent { character: char, tag: Tag, component: char },
+    SelfDecomposition { character: char, tag: Tag },
+    /// The code point column does not name the character of the line
+    CodepointMismatch { line: usize, character: char, codepoint: String },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn entry(k: &char, tag: &Tag) -> String {
+            match tag.to_string() {
+                t if t.is_empty() => k.to_string(),
+                t => format!("{}[{}]", k, t),
+            }
+        }
+        match self {
+            ValidationIssue::Malformed { line } =>
+                write!(f, "line {}: malformed line", line),
+            ValidationIssue::ParseFailure { line, ids } =>
+                write!(f, "line {}: cannot parse IDS {}", line, ids),
+            ValidationIssue::DuplicateKey { line, character, tag } =>
+                write!(f, "line {}: duplicate entry {}", line, entry(character, tag)),
+            ValidationIssue::UnknownComponent { character, tag, component } =>
+                write!(f, "{}: component {} has no entry", entry(character, tag), component),
+            ValidationIssue::SelfDecomposition { character, tag } =>
+                write!(f, "{}: decomposition refers to the character itself", entry(character, tag)),
+            ValidationIssue::CodepointMismatch { line, character, codepoint } =>
+                write!(f, "line {}: code point {} does not match {} (U+{:04X})", line, codepoint, character, *character as u32),
+        }
+    }
+}
+
+/// Which pairs of a ternary composition may be grouped when it is matched
+/// against a binary one, e.g. ⿳abc against ⿱⿱abc (leading) or ⿱a⿱bc
+/// (trailing).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupingBias {
+    #[default]
+    Either,
+    Leading,
+    Trailing,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableStats {
+    pub distinct_chars: usize,
+    pub total_entries: usize,
+    /// Characters whose primary decomposition is not a composition
+    pub atomic_chars: usize,
+    pub max_depth: usize,
+    pub characters_with_multiple_variants: usize,
+}
+
+/// How the decompositions of one character differ between two tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecompositionChange {
+    pub character: char,
+    pub removed: Vec<(Tag, IDS)>,
+    pub added: Vec<(Tag, IDS)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableDiff {
+    pub added: Vec<char>,
+    pub removed: Vec<char>,
+    pub changed: Vec<DecompositionChange>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Work done by a search, for comparing the cost of queries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchMetrics {
+    /// Pairs of nodes compared
+    pub nodes_visited: usize,
+    /// Table entries tested against the query
+    pub candidates_scanned: usize,
+    /// Characters replaced by one of their decompositions
+    pub expansions: usize,
+}
+
+// Index of a tag in the tag pool of a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct TagId(u32);
+
+/// Every distinct tag of a table, stored once. Entries refer to their tag
+/// by its index, so that keys are cheap to hash, copy and keep in the
+/// reverse index.
+#[derive(Default, Debug, Clone)]
+struct TagPool {
+    tags: Vec<Tag>,
+    ids: Map<Tag, TagId>,
+}
+
+impl TagPool {
+    fn intern(&mut self, tag: Tag) -> TagId {
+        if let Some(id) = self.ids.get(&tag) {
+            return *id;
+        }
+        let id = TagId(self.tags.len() as u32);
+        self.tags.push(tag.clone());
+        self.ids.insert(tag, id);
+        id
+    }
+
+    fn id(&self, tag: &Tag) -> Option<TagId> {
+        self.ids.get(tag).copied()
+    }
+
+    fn tag(&self, id: TagId) -> &Tag {
+        &self.tags[id.0 as usize]
+    }
+}
+
+/// The data file of a table opened with `open_lazy`, and where each entry
+/// is in it.
+#[derive(Clone)]
+pub(crate) struct LazySource {
+    text: Arc<dyn AsRef<str> + Send + Sync>,
+    /// Line number and byte range of the decomposition of each entry
+    fields: Map<(char, TagId), (usize, Range<usize>)>,
+}
+
+impl LazySource {
+    fn parse(&self, key: &(char, TagId)) -> Option<IDS> {
+        let (line_no, range) = self.fields.get(key)?;
+        match parse_tagged(&(*self.text).as_ref()[range.clone()]) {
+            Ok(tids) => Some(tids.ids),
+            Err(_) => {
+                warn!("Cannot parse IDS on line {}", line_no);
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LazySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazySource").field("entries", &self.fields.len()).finish_non_exhaustive()
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct IDSTable {
+    table: Map<(char, TagId), Slot>,
+    tags: Map<char, Vec<TagId>>,
+    tag_pool: TagPool,
+    preferred_region: Option<String>,
+    preferred_kind: Option<DecompositionKind>,
+    equivalences: Vec<Set<char>>,
+    fuzzy_components: bool,
+    grouping_bias: GroupingBias,
+    strokes: Map<char, u32>,
+    /// Each radical with the forms it takes as a component
+    radicals: Map<char, Vec<char>>,
+    frequencies: Map<char, u64>,
+    normalize: bool,
+    fold_width: bool,
+    exact_variation_selectors: bool,
+    exact_modifiers: bool,
+    lenient_variation_indicators: bool,
+    loose_overlay: bool,
+    child_subcomponent: bool,
+    /// For each leaf character, the entries whose decomposition has it
+    parents: Map<char, Vec<(char, TagId)>>,
+    /// The entries with a `〾`, whose leaves under it are in `parents` too
+    variant_entries: Set<(char, TagId)>,
+    /// Components reachable from each character, if precomputed
+    closures: Map<char, Set<char>>,
+    /// Where the entries not parsed yet are, for a table opened lazily
+    lazy: Option<LazySource>,
+}
+
+impl IDSTable {
+    #[cfg(feature = "std")]
+    pub fn load_file<P: AsRef<Path>>(path: P) -> io::Result<IDSTable> {
+        IDSTable::load_from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Loads the table from any line source, such as stdin or a stream.
+    #[cfg(feature = "std")]
+    pub fn load_from_reader<R: BufRead>(reader: R) -> io::Result<IDSTable> {
+        let mut table = IDSTable::default();
+        for (i, line) in reader.lines().enumerate() {
+            table.ingest_line(i + 1, &line?, &mut vec![]);
+        }
+        Ok(table)
+    }
+
+    pub fn load_from_string(content: &str) -> Result<IDSTable, LoadError> {
+        IDSTable::load_from_string_with_options(content, &LoadOptions::default())
+    }
+
+    /// Loads a file laid out as described by `options`.
+    #[cfg(feature = "std")]
+    pub fn load_file_with_options<P: AsRef<Path>>(path: P, options: &LoadOptions) -> io::Result<IDSTable> {
+        let mut table = IDSTable::default();
+        for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            table.ingest_line_with(i + 1, &line?, options, &mut vec![]);
+        }
+        Ok(table)
+    }
+
+    /// Loads `path`, keeping only the decompositions of `regions` and the
+    /// untagged ones, e.g. `&["J"]` for an application that only needs
+    /// Japanese forms.
+    #[cfg(feature = "std")]
+    pub fn load_file_regions<P: AsRef<Path>>(path: P, regions: &[&str]) -> io::Result<IDSTable> {
+        let options = LoadOptions { regions: Some(regions.iter().map(|r| r.to_string()).collect()), ..LoadOptions::default() };
+        IDSTable::load_file_with_options(path, &options)
+    }
+
+    pub fn load_from_string_with_options(content: &str, options: &LoadOptions) -> Result<IDSTable, LoadError> {
+        let mut table = IDSTable::default();
+        for (i, line) in content.lines().enumerate() {
+            table.ingest_line_with(i + 1, line, options, &mut vec![]);
+        }
+        Ok(table)
+    }
+
+    /// Loads `path` like `load_file`, but also reports every problem found
+    /// in the data instead of silently skipping it.
+    #[cfg(feature = "std")]
+    pub fn validate_file<P: AsRef<Path>>(path: P) -> io::Result<(IDSTable, Vec<ValidationIssue>)> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let mut table = IDSTable::default();
+        let mut issues = vec![];
+        for (i, line) in reader.lines().enumerate() {
+            table.ingest_line(i + 1, &line?, &mut issues);
+        }
+        issues.extend(table.validate());
+        Ok((table, issues))
+    }
+
+    /// Writes the table in the format it is loaded from, one character per
+    /// line in code point order. Anonymous tags are left out; they are
+    /// numbered again when the file is loaded.
+    #[cfg(feature = "std")]
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut chars: Vec<char> = self.tags.keys().copied().collect();
+        chars.sort_unstable();
+        for k in chars {
+            write!(writer, "U+{:04X}\t{}", k as u32, k)?;
+            for (tag, ids) in self.variants(k) {
+                write!(writer, "\t{}", ids)?;
+                if matches!(&tag, Tag::Variant(regions) if !regions.is_empty()) {
+                    write!(writer, "[{}]", tag)?;
+                }
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn ingest_line(&mut self, line_no: usize, line: &str, issues: &mut Vec<ValidationIssue>) {
+        self.ingest_line_with(line_no, line, &LoadOptions::default(), issues)
+    }
+
+    fn ingest_line_with(&mut self, line_no: usize, line: &str, options: &LoadOptions, issues: &mut Vec<ValidationIssue>) {
+        let Some((char, fields)) = line_fields(line_no, line, options, issues) else {
+            return;
+        };
+        for ids_str in fields {
+            let Ok(tids) = parse_tagged(ids_str) else {
+                warn!("Cannot parse IDS on line {}", line);
+                issues.push(ValidationIssue::ParseFailure { line: line_no, ids: ids_str.to_string() });
+                continue;
+            };
+            let (char, ids) = if self.normalize {
+                (normalize_char(char), tids.ids.map_chars(normalize_char))
+            } else {
+                (char, tids.ids)
+            };
+            let (tag, ids) = if self.fold_width {
+                (tids.tag.fold_width(), ids.map_specials(fold_width))
+            } else {
+                (tids.tag, ids)
+            };
+            if !options.keeps(&tag) {
+                continue;
+            }
+            if !self.insert_entry(char, tag.clone(), ids) {
+                issues.push(ValidationIssue::DuplicateKey { line: line_no, character: char, tag });
+            }
+        }
+    }
+
+    /// A table reading its entries from `text`, a data file in the default
+    /// layout. Only the characters and tags are read now; each decomposition
+    /// is parsed when it is first used, and `load_all` parses the rest.
+    pub(crate) fn from_lazy_text(text: Arc<dyn AsRef<str> + Send + Sync>) -> IDSTable {
+        let mut table = IDSTable::default();
+        let mut fields = Map::new();
+        let data: &str = (*text).as_ref();
+        for (i, line) in data.lines().enumerate() {
+            let Some((k, line_fields)) = line_fields(i + 1, line, &LoadOptions::default(), &mut vec![]) else {
+                continue;
+            };
+            for field in line_fields {
+                // the tag is the bracketed end of the field, as `parse_tagged` reads it
+                let tag = field.strip_suffix(']').and_then(|f| f.rsplit_once('[')).map(|(_, tag)| tag.to_string());
+                let (key, _) = table.new_key(k, Tag::from(tag.unwrap_or_default()));
+                let start = field.as_ptr() as usize - data.as_ptr() as usize;
+                fields.insert(key, (i + 1, start..start + field.len()));
+                table.table.insert(key, Slot::new());
+            }
+        }
+        table.lazy = Some(LazySource { text, fields });
+        table
+    }
+
+    /// Parses the entries of a table opened with `open_lazy` that have not
+    /// been used yet and builds the component index, leaving out the ones
+    /// that do not parse, as loading does. The table then works like a
+    /// loaded one. Methods changing the table call this first.
+    pub fn load_all(&mut self) {
+        let Some(lazy) = self.lazy.take() else {
+            return;
+        };
+        for (key, slot) in &self.table {
+            slot.get_or_init(|| lazy.parse(key));
+        }
+        self.table.retain(|_, slot| loaded(slot).is_some());
+        let table = &self.table;
+        self.tags.retain(|k, k_tags| {
+            k_tags.retain(|t| table.contains_key(&(*k, *t)));
+            !k_tags.is_empty()
+        });
+        self.rebuild_index();
+    }
+
+    /// Adds an entry, falling back to an anonymous tag when `(k, tag)` is
+    /// taken. Returns false in that case.
+    fn insert_entry(&mut self, k: char, tag: Tag, ids: IDS) -> bool {
+        let (key, fresh) = self.new_key(k, tag);
+        self.index_entry(key, &ids);
+        self.table.insert(key, Slot::from(Some(ids)));
+        fresh
+    }
+
+    // The key for a new entry of `k` tagged `tag`, or an anonymous one if
+    // that is taken, and which of the two it is
+    fn new_key(&mut self, k: char, tag: Tag) -> ((char, TagId), bool) {
+        let mut id = self.tag_pool.intern(tag);
+        let fresh = !self.table.contains_key(&(k, id));
+        if !fresh {
+            // after pruning, an anonymous tag may be past the number of variants
+            let k_tags = &self.tags[&k];
+            let next = k_tags.iter()
+                .filter_map(|t| match self.tag(*t) {
+                    Tag::Anon(n) => Some(n + 1),
+                    _ => None,
+                })
+                .fold(k_tags.len(), usize::max);
+            id = self.tag_pool.intern(Tag::Anon(next));
+        }
+        self.tags.entry(k).or_default().push(id);
+        ((k, id), fresh)
+    }
+
+    fn index_entry(&mut self, key: (char, TagId), ids: &IDS) {
+        self.closures.clear();
+        let mut leaves = ids.leaves();
+        leaves.sort_unstable();
+        leaves.dedup();
+        for leaf in leaves {
+            self.parents.entry(leaf).or_default().push(key);
+        }
+        if ids.iter_nodes().any(|node| matches!(node, IDS::VariantOf { .. })) {
+            self.variant_entries.insert(key);
+        }
+    }
+
+    fn rebuild_index(&mut self) {
+        self.parents.clear();
+        self.variant_entries.clear();
+        self.closures.clear();
+        let entries: Vec<((char, TagId), IDS)> = self.entries().map(|(key, ids)| (*key, ids.clone())).collect();
+        for (key, ids) in &entries {
+            self.index_entry(*key, ids);
+        }
+    }
+
+    fn entry_ids(&self, key: &(char, TagId)) -> Option<&IDS> {
+        self.slot_ids(key, self.table.get(key)?)
+    }
+
+    // The decomposition in `slot`, parsed first if the table is lazy
+    fn slot_ids<'a>(&'a self, key: &(char, TagId), slot: &'a Slot) -> Option<&'a IDS> {
+        slot.get_or_init(|| self.lazy.as_ref().and_then(|lazy| lazy.parse(key))).as_ref()
+    }
+
+    // Every entry with its decomposition, without those that do not parse
+    fn entries(&self) -> impl Iterator<Item = (&(char, TagId), &IDS)> {
+        self.table.iter().filter_map(|(key, slot)| Some((key, self.slot_ids(key, slot)?)))
+    }
+
+    fn tag(&self, id: TagId) -> &Tag {
+        self.tag_pool.tag(id)
+    }
+
+    // The key of an entry as seen from outside the table
+    fn entry_key(&self, (k, id): (char, TagId)) -> (char, Tag) {
+        (k, self.tag(id).clone())
+    }
+
+    pub fn normalizes(&self) -> bool {
+        self.normalize
+    }
+
+    /// When enabled, characters are Unicode-normalized: the keys and
+    /// decompositions already loaded, later loads, and search queries.
+    pub fn set_normalize(&mut self, normalize: bool) {
+        self.normalize = normalize;
+        if normalize {
+            self.rekey(|k, tag, ids| (normalize_char(k), tag, ids.map_chars(normalize_char)));
+        }
+    }
+
+    pub fn folds_width(&self) -> bool {
+        self.fold_width
+    }
+
+    /// When enabled, fullwidth Latin letters and digits in `{...}` specials
+    /// and region tags are folded to halfwidth, so that `{ABC}` and `{ＡＢＣ}`
+    /// are the same component. Applies to loaded entries, later loads and
+    /// matching.
+    pub fn set_fold_width(&mut self, fold: bool) {
+        self.fold_width = fold;
+        if fold {
+            self.rekey(|k, tag, ids| (k, tag.fold_width(), ids.map_specials(fold_width)));
+        }
+    }
+
+    /// Re-inserts every entry as rewritten by `f`, in key order.
+    fn rekey(&mut self, f: impl Fn(char, Tag, IDS) -> (char, Tag, IDS)) {
+        self.load_all();
+        let table = core::mem::take(&mut self.table);
+        let mut entries: Vec<((char, Tag), IDS)> = table.into_iter()
+            .filter_map(|(key, slot)| Some((self.entry_key(key), slot.into_inner()??)))
+            .collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.tags.clear();
+        self.parents.clear();
+        self.variant_entries.clear();
+        self.tag_pool = TagPool::default();
+        for ((k, tag), ids) in entries {
+            let (k, tag, ids) = f(k, tag, ids);
+            self.insert_entry(k, tag, ids);
+        }
+    }
+
+    /// Structural problems of the loaded entries: components that have no
+    /// entry of their own, and decompositions referring back to their own
+    /// character. A character decomposing to itself is how atomic components
+    /// are written, so that is only reported when it also has a real
+    /// decomposition.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut entries: Vec<(char, &Tag, &IDS)> = self.entries().map(|((k, id), ids)| (*k, self.tag(*id), ids)).collect();
+        entries.sort_by(|(a, s, _), (b, t, _)| a.cmp(b).then(s.cmp(t)));
+        entries.into_iter()
+            .flat_map(|(k, tag, ids)| self.validate_ids(k, tag, ids))
+            .collect()
+    }
+
+    /// Checks `ids` as a decomposition of `k` before it is added to the
+    /// table: every component needs an entry, and the decomposition must
+    /// not refer to `k` itself. Being atomic (`k` decomposing to itself) is
+    /// only a problem when `k` also has other decompositions.
+    pub fn validate_ids(&self, k: char, tag: &Tag, ids: &IDS) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+        if ids == &IDS::Char(k) {
+            if !self.expand_char(k).is_empty() {
+                issues.push(ValidationIssue::SelfDecomposition { character: k, tag: tag.clone() });
+            }
+            return issues;
+        }
+        let leaves = ids.leaves();
+        if leaves.contains(&k) {
+            issues.push(ValidationIssue::SelfDecomposition { character: k, tag: tag.clone() });
+        }
+        let mut reported = vec![];
+        for leaf in leaves {
+            if !self.contains_char(leaf) && !reported.contains(&leaf) {
+                reported.push(leaf);
+                issues.push(ValidationIssue::UnknownComponent { character: k, tag: tag.clone(), component: leaf });
+            }
+        }
+        issues
+    }
+
+    /// The entries whose decomposition, as written, uses the un-encoded
+    /// component `{special}`, in code point order. Unlike a search, other
+    /// characters are not expanded: these are the lines to edit once the
+    /// component gets a code point.
+    pub fn entries_with_special(&self, special: &str) -> Vec<(char, Tag)> {
+        let mut entries: Vec<(char, Tag)> = self.entries()
+            .filter(|(_, ids)| ids.specials().into_iter().any(|s| self.specials_equal(s, special)))
+            .map(|(key, _)| self.entry_key(*key))
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    /// Every un-encoded component with the number of times it is written
+    /// in a decomposition, the most used first.
+    pub fn specials(&self) -> Vec<(String, usize)> {
+        let mut counts: Map<&str, usize> = Map::new();
+        for s in self.entries().flat_map(|(_, ids)| ids.specials()) {
+            *counts.entry(s).or_default() += 1;
+        }
+        let mut specials: Vec<(String, usize)> = counts.into_iter().map(|(s, n)| (s.to_string(), n)).collect();
+        specials.sort_by(|(a, n), (b, m)| m.cmp(n).then(a.cmp(b)));
+        specials
+    }
+
+    /// Components used in some decomposition that have no entry of their
+    /// own, the most referenced first.
+    pub fn orphan_components(&self) -> Vec<char> {
+        let mut orphans: Vec<(usize, char)> = self.parents.iter()
+            .filter(|(c, _)| !self.contains_char(**c))
+            .map(|(c, users)| (users.len(), *c))
+            .collect();
+        orphans.sort_by(|(n, a), (m, b)| m.cmp(n).then(a.cmp(b)));
+        orphans.into_iter().map(|(_, c)| c).collect()
+    }
+
+    /// Maps every atomic component, one that does not decompose any
+    /// further, to the characters containing it in code point order, as
+    /// `find` with that component lists them (itself included). When the
+    /// component index does not apply, each component is found by testing
+    /// every entry, which is much slower.
+    pub fn export_index(&self) -> Map<char, Vec<char>> {
+        if !self.index_applies() {
+            let mut components: Vec<char> = self.entries().flat_map(|(_, ids)| ids.leaves()).collect();
+            components.sort_unstable();
+            components.dedup();
+            return components.into_iter()
+                .filter(|&c| self.expand_char(c).is_empty())
+                .map(|c| {
+                    let needle = IDS::Char(c);
+                    let mut chars: Vec<char> = self.iter()
+                        .filter(|(_, ids)| self.ids_has_subcomponent(ids, &needle))
+                        .map(|((k, _), _)| k)
+                        .collect();
+                    chars.sort_unstable();
+                    chars.dedup();
+                    (c, chars)
+                })
+                .collect();
+        }
+        self.parents.keys()
+            .filter(|c| self.expand_char(**c).is_empty())
+            .map(|&c| {
+                let mut chars: Vec<char> = self.find_leaf_only(c).into_iter().map(|(k, _)| k).collect();
+                chars.dedup();
+                (c, chars)
+            })
+            .collect()
+    }
+
+    pub fn preferred_region(&self) -> Option<&str> {
+        self.preferred_region.as_deref()
+    }
+
+    /// Makes variants carrying `region` the first choice when a character
+    /// is expanded into its decomposition.
+    pub fn set_preferred_region(&mut self, region: Option<String>) {
+        self.preferred_region = region;
+        self.closures.clear();
+    }
+
+    pub fn preferred_kind(&self) -> Option<DecompositionKind> {
+        self.preferred_kind
+    }
+
+    /// Expands characters that have decompositions of `kind` only into
+    /// those, leaving out their decompositions marked with the other kind.
+    pub fn set_preferred_kind(&mut self, kind: Option<DecompositionKind>) {
+        self.preferred_kind = kind;
+        self.closures.clear();
+    }
+
+    /// Loads sets of easily confused components, one set per line (e.g.
+    /// `己已巳`). Whitespace between characters is ignored.
+    pub fn load_equivalences_from_string(&mut self, content: &str) {
+        for line in content.lines() {
+            let set: Set<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+            if set.len() > 1 {
+                self.equivalences.push(set);
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load_equivalences_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let content = std::fs::read_to_string(path)?;
+        self.load_equivalences_from_string(&content);
+        Ok(())
+    }
+
+    /// Loads the forms radicals take as components, one radical per line
+    /// followed by its other forms (e.g. `水氵氺`).
+    pub fn load_radicals_from_string(&mut self, content: &str) {
+        for line in content.lines() {
+            let mut forms: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+            if forms.is_empty() {
+                continue;
+            }
+            let radical = forms[0];
+            forms.dedup();
+            self.radicals.insert(radical, forms);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load_radicals_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let content = std::fs::read_to_string(path)?;
+        self.load_radicals_from_string(&content);
+        Ok(())
+    }
+
+    /// Characters containing `radical`, in any of its loaded forms, with
+    /// `residual_strokes` strokes besides those of the form, as listed in
+    /// a dictionary under that radical. Needs stroke data.
+    pub fn by_radical(&self, radical: char, residual_strokes: u32) -> Vec<char> {
+        let forms = self.radicals.get(&radical).cloned().unwrap_or_else(|| vec![radical]);
+        let mut result = vec![];
+        for form in forms {
+            let Some(form_strokes) = self.stroke_count(form) else {
+                continue;
+            };
+            for (k, _) in self.find_leaf_only(form) {
+                if self.stroke_count(k).and_then(|n| n.checked_sub(form_strokes)) == Some(residual_strokes) {
+                    result.push(k);
+                }
+            }
+        }
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Loads stroke counts, one `char count` pair per line. A leading
+    /// `U+XXXX` column is allowed.
+    pub fn load_strokes_from_string(&mut self, content: &str) {
+        for (k, n) in parse_char_numbers(content) {
+            self.strokes.insert(k, n as u32);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load_strokes_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let content = std::fs::read_to_string(path)?;
+        self.load_strokes_from_string(&content);
+        Ok(())
+    }
+
+    pub fn stroke_count(&self, k: char) -> Option<u32> {
+        self.strokes.get(&k).copied()
+    }
+
+    /// Loads character frequencies (higher is more frequent), in the same
+    /// format as `load_strokes_from_string`.
+    pub fn load_frequencies_from_string(&mut self, content: &str) {
+        self.frequencies.extend(parse_char_numbers(content));
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load_frequencies_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let content = std::fs::read_to_string(path)?;
+        self.load_frequencies_from_string(&content);
+        Ok(())
+    }
+
+    pub fn frequency(&self, k: char) -> Option<u64> {
+        self.frequencies.get(&k).copied()
+    }
+
+    /// When enabled, components in the same equivalence set compare equal
+    /// during matching.
+    pub fn set_fuzzy_components(&mut self, fuzzy: bool) {
+        self.fuzzy_components = fuzzy;
+    }
+
+    /// When enabled, a character with a variation selector only matches the
+    /// same character with the same selector.
+    pub fn set_exact_variation_selectors(&mut self, exact: bool) {
+        self.exact_variation_selectors = exact;
+    }
+
+    pub fn exact_variation_selectors(&self) -> bool {
+        self.exact_variation_selectors
+    }
+
+    // Whether `find_leaf_only` answers like testing every entry; the index
+    // tells neither variation selectors nor modifiers apart, and holds
+    // the decompositions of both kinds
+    pub(crate) fn index_applies(&self) -> bool {
+        self.lazy.is_none() && !self.exact_variation_selectors && !self.exact_modifiers && self.preferred_kind.is_none()
+    }
+
+    /// When enabled, a mirrored or rotated component only matches the same
+    /// component with the same modifier.
+    pub fn set_exact_modifiers(&mut self, exact: bool) {
+        self.exact_modifiers = exact;
+        self.closures.clear();
+    }
+
+    /// When enabled, a component marked with `〾` also matches its base, so
+    /// `〾青` matches `青`. Otherwise it only matches another `〾` of the
+    /// same base.
+    pub fn set_lenient_variation_indicators(&mut self, lenient: bool) {
+        self.lenient_variation_indicators = lenient;
+        self.closures.clear();
+    }
+
+    /// When enabled, a `⿻` pattern in partial matching is only a set of
+    /// components: `⿻ab` matches any part containing both a and b, however
+    /// they are arranged. This is looser than matching the overlay itself;
+    /// for example `⿻禾刂` matches 剩 (⿰乘刂).
+    pub fn set_loose_overlay(&mut self, loose: bool) {
+        self.loose_overlay = loose;
+    }
+
+    // The leaves of `ids` that matching looks through to
+    fn matched_leaves(&self, ids: &IDS) -> Vec<char> {
+        if self.lenient_variation_indicators {
+            ids.leaves()
+        } else {
+            ids.plain_leaves()
+        }
+    }
+
+    /// When enabled, a child of a pattern composition also matches when it
+    /// is only part of the corresponding child, so `⿰氵青` matches
+    /// `⿰氵⿱青月`. Positions still have to agree.
+    pub fn set_child_subcomponent(&mut self, enabled: bool) {
+        self.child_subcomponent = enabled;
+    }
+
+    pub fn set_grouping_bias(&mut self, bias: GroupingBias) {
+        self.grouping_bias = bias;
+    }
+
+    fn chars_equal(&self, a: char, b: char) -> bool {
+        a == b || (self.fuzzy_components && self.equivalences.iter().any(|set| set.contains(&a) && set.contains(&b)))
+    }
+
+    /// The variant tags of `k`, with those of the preferred region first.
+    /// Tags of the other kind are dropped when `k` has the preferred one.
+    /// Borrowed from the table unless there is a preference to apply.
+    fn variant_tags(&self, k: char) -> Cow<'_, [TagId]> {
+        let Some(k_tags) = self.tags.get(&k) else {
+            return Cow::Borrowed(&[]);
+        };
+        if self.preferred_kind.is_none() && self.preferred_region.is_none() {
+            return Cow::Borrowed(k_tags);
+        }
+        let mut k_tags = k_tags.clone();
+        if let Some(kind) = self.preferred_kind.filter(|kind| k_tags.iter().any(|t| self.tag(*t).kind() == Some(*kind))) {
+            k_tags.retain(|t| self.tag(*t).kind().is_none_or(|k| k == kind));
+        }
+        if let Some(region) = &self.preferred_region {
+            k_tags.sort_by_key(|t| !self.tag(*t).has_region(region));
+        }
+        Cow::Owned(k_tags)
+    }
+
+    /// `variant_tags`, keeping only the variants of `region` when `k` has
+    /// one there.
+    fn region_tags(&self, k: char, region: Option<&str>) -> Cow<'_, [TagId]> {
+        let mut k_tags = self.variant_tags(k);
+        if let Some(region) = region.filter(|r| k_tags.iter().any(|t| self.tag(*t).has_region(r))) {
+            k_tags.to_mut().retain(|t| self.tag(*t).has_region(region));
+        }
+        k_tags
+    }
+
+    fn specials_equal(&self, a: &str, b: &str) -> bool {
+        a == b || (self.fold_width && fold_width(a) == fold_width(b))
+    }
+
+    pub fn ids_match(&self, a: &IDS, b: &IDS, wildcard_k: char) -> bool {
+        self.ids_match_metered(a, b, wildcard_k, &mut SearchMetrics::default())
+    }
+
+    /// `ids_match`, adding the work it does to `metrics`.
+    pub fn ids_match_metered(&self, a: &IDS, b: &IDS, wildcard_k: char, metrics: &mut SearchMetrics) -> bool {
+        self.match_expanding(a, b, wildcard_k, None, metrics)
+    }
+
+    /// `ids_match`, but a component with variants of `region` is only
+    /// expanded into those, as for the query `match ⿰氵青@J`.
+    pub fn ids_match_in_region(&self, a: &IDS, b: &IDS, wildcard_k: char, region: &str) -> bool {
+        self.match_expanding(a, b, wildcard_k, Some(region), &mut SearchMetrics::default())
+    }
+
+    /// `ids_match_in_region`, adding the work it does to `metrics`.
+    pub fn ids_match_in_region_metered(&self, a: &IDS, b: &IDS, wildcard_k: char, region: &str, metrics: &mut SearchMetrics) -> bool {
+        self.match_expanding(a, b, wildcard_k, Some(region), metrics)
+    }
+
+    fn match_expanding(&self, a: &IDS, b: &IDS, wildcard_k: char, region: Option<&str>, metrics: &mut SearchMetrics) -> bool {
+        metrics.nodes_visited += 1;
+        use IDS::*;
+        match (a, b) {
+            (Char(a), _) if a == &wildcard_k => true,
+            (_, Char(b)) if b == &wildcard_k => true,
+            (CharWithVS { base: x, vs: xv }, CharWithVS { base: y, vs: yv }) =>
+                self.chars_equal(*x, *y) && (xv == yv || !self.exact_variation_selectors),
+            (CharWithVS { base, .. }, _) if !self.exact_variation_selectors => self.match_expanding(&Char(*base), b, wildcard_k, region, metrics),
+            (_, CharWithVS { base, .. }) if !self.exact_variation_selectors => self.match_expanding(a, &Char(*base), wildcard_k, region, metrics),
+            (CharWithVS { .. }, _) | (_, CharWithVS { .. }) => false,
+            (Modified { base: x, modifier: xm }, Modified { base: y, modifier: ym }) if xm == ym || !self.exact_modifiers =>
+                self.match_expanding(x, y, wildcard_k, region, metrics),
+            (Modified { base, .. }, _) if !self.exact_modifiers => self.match_expanding(base, b, wildcard_k, region, metrics),
+            (_, Modified { base, .. }) if !self.exact_modifiers => self.match_expanding(a, base, wildcard_k, region, metrics),
+            (Modified { .. }, _) | (_, Modified { .. }) => false,
+            (VariantOf { base: x }, VariantOf { base: y }) => self.match_expanding(x, y, wildcard_k, region, metrics),
+            (VariantOf { base }, _) if self.lenient_variation_indicators => self.match_expanding(base, b, wildcard_k, region, metrics),
+            (_, VariantOf { base }) if self.lenient_variation_indicators => self.match_expanding(a, base, wildcard_k, region, metrics),
+            (VariantOf { .. }, _) | (_, VariantOf { .. }) => false,
+            (OneOf(cs), _) => cs.iter().any(|c| self.match_expanding(&Char(*c), b, wildcard_k, region, metrics)),
+            (_, OneOf(cs)) => cs.iter().any(|c| self.match_expanding(a, &Char(*c), wildcard_k, region, metrics)),
+            (Special(a), Special(b)) => self.specials_equal(a, b),
+            (Entity(a), Entity(b)) => a == b,
+            (Char(a), Char(b)) => self.chars_equal(*a, *b),
+            (Char(k), Composition { .. }) => {
+                for &k_tag in self.region_tags(*k, region).iter() {
+                    if let Some(k_components) = self.entry_ids(&(*k, k_tag)) {
+                        if k_components == &IDS::Char(*k) {
+                            continue;
+                        }
+                        metrics.expansions += 1;
+                        if self.match_expanding(k_components, b, wildcard_k, region, metrics) {
+                            return true;
+                        }
+                    }
+                }
+                false
+            }
+            (Composition { .. }, Char(_)) => {
+                self.match_expanding(b, a, wildcard_k, region, metrics)
+            }
+            (Composition { idc: xc, children: xs, .. }, Composition { idc: yc, children: ys, .. })
+                if ends_with_ellipsis(ys) || ends_with_ellipsis(xs) => {
+                let (haystack, prefix) = if ends_with_ellipsis(ys) { (xs, &ys[..ys.len() - 1]) } else { (ys, &xs[..xs.len() - 1]) };
+                // ⿰氵… also matches ⿲氵木木: only the direction has to agree
+                let compatible = xc == yc || (xc.is_same_direction(*yc) && xc.direction().is_linear());
+                compatible && prefix.len() <= haystack.len()
+                    && prefix.iter().zip(haystack.iter()).all(|(p, h)| self.match_expanding(h, p, wildcard_k, region, metrics))
+            }
+            // the frame decides the shape, so surround IDCs are
+            // interchangeable as long as the frame is the same character;
+            // a wildcard frame leaves the IDC to decide
+            (Composition { idc: xc, children: xs }, Composition { idc: yc, children: ys })
+                if xc != yc && xc.is_surround() && yc.is_surround() => {
+                match (xs.as_slice(), ys.as_slice()) {
+                    ([fx @ Char(x), ix], [fy @ Char(y), iy]) if *x != wildcard_k && *y != wildcard_k => self.match_expanding(fx, fy, wildcard_k, region, metrics) && self.match_expanding(ix, iy, wildcard_k, region, metrics),
+                    _ => false,
+                }
+            }
+            (x @ Composition { idc: xc, children: xs, .. }, y @ Composition { idc: yc, children: ys, .. }) => {
+                if xc == yc {
+                    // children missing from a partial pattern match anything
+                    if xs.iter().zip(ys.iter()).all(|(x, y)| {
+                        self.match_expanding(x, y, wildcard_k, region, metrics)
+                            || (self.child_subcomponent && self.subcomponent_expanding(x, y, wildcard_k, None, region, metrics))
+                    }) {
+                        return true;
+                    }
+                    if xc.arity() != 3 || xs.len() != 3 || ys.len() != 3 {
+                        return false;
+                    }
+                    // try to match ⿳ab⿱cd with ⿳⿱abcd by regrouping both sides
+                    let xs = flatten_direction(x, xc.direction());
+                    let ys = flatten_direction(y, xc.direction());
+                    return xs.len() == ys.len()
+                        && xs.iter().zip(ys.iter()).all(|(x, y)| self.match_expanding(x, y, wildcard_k, region, metrics));
+                }
+                // try to match ⿳abc with ⿱de, on either side
+                let (tc, ts, ds, three_in_haystack) = match (xc.arity(), yc.arity()) {
+                    (3, 2) => (xc, xs, ys, true),
+                    (2, 3) => (yc, ys, xs, false),
+                    _ => return false,
+                };
+                if !xc.is_same_direction(*yc) || ts.len() != 3 || ds.len() != 2 {
+                    return false;
+                }
+                let a = ts[0].clone();
+                let b = ts[1].clone();
+                let c = ts[2].clone();
+                let d = &ds[0];
+                let e = &ds[1];
+                // keep the haystack first whichever side is regrouped
+                let pair = |three: &IDS, two: &IDS, metrics: &mut SearchMetrics| if three_in_haystack {
+                    self.match_expanding(three, two, wildcard_k, region, metrics)
+                } else {
+                    self.match_expanding(two, three, wildcard_k, region, metrics)
+                };
+                let leading = |metrics: &mut SearchMetrics| {
+                    let ab = Composition { idc: tc.reduce().unwrap(), children: vec![a.clone(), b.clone()] };
+                    pair(&ab, d, metrics) && pair(&c, e, metrics)
+                };
+                let trailing = |metrics: &mut SearchMetrics| {
+                    let bc = Composition { idc: tc.reduce().unwrap(), children: vec![b.clone(), c.clone()] };
+                    pair(&a, d, metrics) && pair(&bc, e, metrics)
+                };
+                match self.grouping_bias {
+                    GroupingBias::Either => leading(metrics) || trailing(metrics),
+                    GroupingBias::Leading => leading(metrics),
+                    GroupingBias::Trailing => trailing(metrics),
+                }
+            }
+            _ => false,
+        }
+    }
+
+    pub fn ids_has_matching_subcomponent(&self, a: &IDS, b: &IDS, wildcard_k: char) -> bool {
+        self.ids_has_matching_subcomponent_at_depth(a, b, wildcard_k, None)
+    }
+
+    /// Like `ids_has_matching_subcomponent`, but with `Some(depth)` only
+    /// counts matches `depth` levels below the root of `a`: 0 is `a`
+    /// itself, 1 its children and so on. A character and its decomposition
+    /// are at the same level.
+    pub fn ids_has_matching_subcomponent_at_depth(&self, a: &IDS, b: &IDS, wildcard_k: char, depth: Option<usize>) -> bool {
+        self.subcomponent_expanding(a, b, wildcard_k, depth, None, &mut SearchMetrics::default())
+    }
+
+    /// `ids_has_matching_subcomponent_at_depth`, expanding characters as
+    /// `match_expanding` does with `region` and adding the work to `metrics`.
+    fn subcomponent_expanding(&self, a: &IDS, b: &IDS, wildcard_k: char, depth: Option<usize>, region: Option<&str>, metrics: &mut SearchMetrics) -> bool {
+        use IDS::*;
+        let here = depth.is_none_or(|d| d == 0);
+        // None when the match has to be further down than the children
+        let below = match depth {
+            None => Some(None),
+            Some(d) => d.checked_sub(1).map(Some),
+        };
+        if let (true, Composition { idc, children }) = (self.loose_overlay, b) {
+            if idc.direction() == Direction::Overlaid {
+                if here && children.iter().all(|c| self.subcomponent_expanding(a, c, wildcard_k, None, region, metrics)) {
+                    return true;
+                }
+                // whatever a part contains, so does the whole
+                if depth.is_none() {
+                    return false;
+                }
+            }
+        }
+        if here && self.match_expanding(a, b, wildcard_k, region, metrics) {
+            return true;
+        }
+        match (a, b) {
+            (Char(a), _) if a == &wildcard_k => here,
+            (_, Char(b)) if b == &wildcard_k => here,
+            (Special(a), Special(b)) => here && self.specials_equal(a, b),
+            (Entity(a), Entity(b)) => here && a == b,
+            (CharWithVS { base, .. }, _) if !self.exact_variation_selectors => self.subcomponent_expanding(&Char(*base), b, wildcard_k, depth, region, metrics),
+            (Modified { base, .. }, _) if !self.exact_modifiers => self.subcomponent_expanding(base, b, wildcard_k, depth, region, metrics),
+            (VariantOf { base }, _) if self.lenient_variation_indicators => self.subcomponent_expanding(base, b, wildcard_k, depth, region, metrics),
+            (Special(_), _) | (Entity(_), _) | (OneOf(_), _) | (CharWithVS { .. }, _) | (Modified { .. }, _) | (VariantOf { .. }, _) => false,
+            (Char(ka), _) => {
+                if let Char(kb) = b {
+                    if here && self.chars_equal(*ka, *kb) {
+                        return true;
+                    }
+                }
+                for &tag in self.region_tags(*ka, region).iter() {
+                    if let Some(asub) = self.entry_ids(&(*ka, tag)).filter(|ids| **ids != Char(*ka)) {
+                        metrics.expansions += 1;
+                        if self.subcomponent_expanding(asub, b, wildcard_k, depth, region, metrics) {
+                            return true;
+                        }
+                    }
+                }
+                false
+            },
+            (Composition { idc: xc, children: xs }, b) => {
+                let Some(below) = below else {
+                    return false;
+                };
+                for x in xs {
+                    if self.subcomponent_expanding(x, b, wildcard_k, below, region, metrics) {
+                        return true;
+                    }
+                }
+                // ⿱ab is also a part of ⿳abc: try runs of adjacent parts
+                // along the same direction as groups of their own. A run
+                // of only some of the parts sits at the level of the parts.
+                let Composition { idc: yc, children: ys } = b else {
+                    return false;
+                };
+                let dir = xc.direction();
+                if !dir.is_linear() || !yc.is_same_direction(*xc) {
+                    return false;
+                }
+                let parts_level = below.is_none_or(|d| d == 0);
+                let parts = flatten_direction(a, dir);
+                let Some(group_idc) = IDC::of_direction(dir, ys.len()) else {
+                    return false;
+                };
+                if parts_level && parts.len() > ys.len() && parts.windows(ys.len()).any(|run| {
+                    let group = Composition { idc: group_idc, children: run.iter().map(|&p| p.clone()).collect() };
+                    self.match_expanding(&group, b, wildcard_k, region, metrics)
+                }) {
+                    return true;
+                }
+                // the parts may be grouped differently on either side, with
+                // some of them stored as characters: spread those out too
+                let parts = self.flatten_direction_expanded(a, dir);
+                let wanted = self.flatten_direction_expanded(b, dir);
+                let level = if parts.len() == wanted.len() { here } else { parts_level };
+                level && parts.len() >= wanted.len() && parts.windows(wanted.len()).any(|run| {
+                    run.iter().zip(&wanted).all(|(p, w)| self.match_expanding(p, w, wildcard_k, region, metrics))
+                })
+            }
+        }
+    }
+
+    /// Whether `ids`, or a decomposition of it if it is a character, has
+    /// the IDC of `pattern` and parts matching its children.
+    pub fn match_mixed(&self, ids: &IDS, pattern: &MixedPattern, wildcard_k: char) -> bool {
+        self.match_mixed_at_depth(ids, pattern, wildcard_k, MAX_EXPANSION_DEPTH)
+    }
+
+    fn match_mixed_at_depth(&self, ids: &IDS, pattern: &MixedPattern, wildcard_k: char, depth: usize) -> bool {
+        match ids {
+            IDS::Char(k) if depth > 0 => {
+                self.expand_char(*k).iter().any(|sub| self.match_mixed_at_depth(&sub.ids, pattern, wildcard_k, depth - 1))
+            }
+            IDS::Composition { idc, children } if *idc == pattern.idc => {
+                children.iter().zip(&pattern.children).all(|(part, child)| match child {
+                    ChildPattern::Exact(p) => self.ids_match(part, p, wildcard_k),
+                    ChildPattern::Any => true,
+                    ChildPattern::Contains(p) => self.ids_has_matching_subcomponent(part, p, wildcard_k),
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// Like `flatten_direction`, but also spreads out characters whose
+    /// primary decomposition goes in the same direction.
+    fn flatten_direction_expanded(&self, ids: &IDS, dir: Direction) -> Vec<IDS> {
+        match ids {
+            IDS::Composition { idc, children } if idc.direction() == dir && dir.is_linear() => {
+                children.iter().flat_map(|c| self.flatten_direction_expanded(c, dir)).collect()
+            }
+            IDS::Char(k) => match self.primary_decomposition(*k) {
+                Some(d @ IDS::Composition { idc, .. }) if idc.direction() == dir && dir.is_linear() && !d.leaves().contains(k) => {
+                    self.flatten_direction_expanded(d, dir)
+                }
+                _ => vec![ids.clone()],
+            },
+            _ => vec![ids.clone()],
+        }
+    }
+
+    fn expand_char(&self, k: char) -> Vec<TaggedIDS> {
+        let mut result = vec![];
+        for &tag in self.variant_tags(k).iter() {
+            if let Some(components) = self.entry_ids(&(k, tag)) {
+                if components != &IDS::Char(k) {
+                    result.push(TaggedIDS {
+                        tag: self.tag(tag).clone(),
+                        ids: components.clone(),
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// Fully expands `ids` into its atomic leaves (in reading order), using
+    /// the first non-trivial decomposition of every character on the way.
+    pub fn atomic_leaves(&self, ids: &IDS) -> Vec<IDS> {
+        let mut leaves = vec![];
+        self.collect_atomic_leaves(ids, &mut leaves, &mut vec![]);
+        leaves
+    }
+
+    fn collect_atomic_leaves(&self, ids: &IDS, leaves: &mut Vec<IDS>, expanding: &mut Vec<char>) {
+        match ids {
+            IDS::Special(_) | IDS::Entity(_) | IDS::OneOf(_) | IDS::CharWithVS { .. } | IDS::Modified { .. } | IDS::VariantOf { .. } => leaves.push(ids.clone()),
+            IDS::Char(k) => {
+                // guard against cyclic data
+                if expanding.contains(k) {
+                    leaves.push(ids.clone());
+                    return;
+                }
+                let Some(sub) = self.expand_char(*k).into_iter().next() else {
+                    leaves.push(ids.clone());
+                    return;
+                };
+                expanding.push(*k);
+                self.collect_atomic_leaves(&sub.ids, leaves, expanding);
+                expanding.pop();
+            }
+            IDS::Composition { children, .. } => {
+                for c in children {
+                    self.collect_atomic_leaves(c, leaves, expanding);
+                }
+            }
+        }
+    }
+
+    pub fn ids_has_subcomponent(&self, haystack: &IDS, needle: &IDS) -> bool {
+        debug!("has_subcomponent haystack={:?} needle={:?}", haystack, needle);
+        if haystack == needle {
+            return true;
+        }
+        use IDS::*;
+        // a composition needle matches any subtree of the same structure,
+        // however its parts are written
+        if let (Composition { .. }, Composition { .. }) = (haystack, needle) {
+            if self.ids_match(haystack, needle, NO_WILDCARD) {
+                return true;
+            }
+        }
+        match (haystack, needle) {
+            (_, OneOf(cs)) => cs.iter().any(|c| self.ids_has_subcomponent(haystack, &Char(*c))),
+            (CharWithVS { base, .. }, _) if !self.exact_variation_selectors => self.ids_has_subcomponent(&Char(*base), needle),
+            (_, CharWithVS { base, .. }) if !self.exact_variation_selectors => self.ids_has_subcomponent(haystack, &Char(*base)),
+            (Modified { base, .. }, _) if !self.exact_modifiers => self.ids_has_subcomponent(base, needle),
+            (_, Modified { base, .. }) if !self.exact_modifiers => self.ids_has_subcomponent(haystack, base),
+            (VariantOf { base }, _) if self.lenient_variation_indicators => self.ids_has_subcomponent(base, needle),
+            (_, VariantOf { base }) if self.lenient_variation_indicators => self.ids_has_subcomponent(haystack, base),
+            (VariantOf { base: a }, VariantOf { base: b }) => self.ids_match(a, b, NO_WILDCARD),
+            (Special(a), Special(b)) => self.specials_equal(a, b),
+            (Entity(a), Entity(b)) => a == b,
+            (Special(_), _) | (Entity(_), _) | (OneOf(_), _) | (CharWithVS { .. }, _) | (Modified { .. }, _) | (VariantOf { .. }, _) => false,
+            (Char(a), Char(b)) if self.chars_equal(*a, *b) => true,
+            (Char(a), Char(_)) if !self.contains_char(*a) => false,
+            (Char(a), _) => {
+                if let (Char(b), Some(closure)) = (needle, self.closure(*a)) {
+                    return closure.contains(b);
+                }
+                for &tag in self.variant_tags(*a).iter() {
+                    if let Some(a_components) = self.entry_ids(&(*a, tag)) {
+                        if a_components != &IDS::Char(*a) && self.ids_has_subcomponent(a_components, needle) {
+                            return true;
+                        }
+                    }
+                }
+                false
+            },
+            (Composition { children, .. }, _) => {
+                for c in children {
+                    if self.ids_has_subcomponent(c, needle) {
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    /// Caches, for every character, the components reachable by expanding
+    /// its decompositions, so that looking for a single character in it no
+    /// longer expands anything. Characters that still expand after
+    /// `max_depth` levels (such as on cyclic data) are left out and searched
+    /// as before. Any change to the entries, or to a setting that changes
+    /// how characters expand, drops the cache.
+    pub fn precompute_closures(&mut self, max_depth: usize) {
+        let mut closures = Map::new();
+        for &k in self.tags.keys() {
+            if let Some(closure) = self.reachable_components(k, max_depth) {
+                closures.insert(k, closure);
+            }
+        }
+        self.closures = closures;
+    }
+
+    fn reachable_components(&self, k: char, max_depth: usize) -> Option<Set<char>> {
+        let mut reached = Set::new();
+        let mut frontier = vec![k];
+        for _ in 0..max_depth {
+            let mut next = vec![];
+            for c in frontier {
+                for sub in self.expand_char(c) {
+                    next.extend(self.matched_leaves(&sub.ids).into_iter().filter(|&leaf| reached.insert(leaf)));
+                }
+            }
+            if next.is_empty() {
+                return Some(reached);
+            }
+            frontier = next;
+        }
+        frontier.iter().all(|&c| self.expand_char(c).is_empty()).then_some(reached)
+    }
+
+    // The cached closure of `k`, when it answers the same as expanding;
+    // closures look through modifiers, so exact ones are not cached
+    fn closure(&self, k: char) -> Option<&Set<char>> {
+        if self.fuzzy_components || self.exact_variation_selectors || self.exact_modifiers {
+            return None;
+        }
+        self.closures.get(&k)
+    }
+
+    /// How many times `needle` occurs in `haystack`, expanding characters
+    /// until it is found. A character with several decompositions counts as
+    /// the one with the most occurrences.
+    pub fn subcomponent_count(&self, haystack: &IDS, needle: &IDS) -> usize {
+        self.count_occurrences(haystack, needle, &mut Map::new())
+    }
+
+    // `counted` remembers the count of every character expanded so far
+    fn count_occurrences(&self, haystack: &IDS, needle: &IDS, counted: &mut Map<char, usize>) -> usize {
+        match (haystack, needle) {
+            (IDS::Char(a), IDS::Char(b)) if self.chars_equal(*a, *b) => 1,
+            _ if haystack == needle => 1,
+            (IDS::Composition { children, .. }, _) => children.iter().map(|c| self.count_occurrences(c, needle, counted)).sum(),
+            (IDS::Char(k), _) => {
+                if let Some(n) = counted.get(k) {
+                    return *n;
+                }
+                // a cycle back to `k` counts nothing
+                counted.insert(*k, 0);
+                let n = self.expand_char(*k).iter()
+                    .map(|sub| self.count_occurrences(&sub.ids, needle, counted))
+                    .max()
+                    .unwrap_or(0);
+                counted.insert(*k, n);
+                n
+            }
+            (IDS::CharWithVS { base, .. }, _) if !self.exact_variation_selectors => self.count_occurrences(&IDS::Char(*base), needle, counted),
+            (IDS::Modified { base, .. }, _) if !self.exact_modifiers => self.count_occurrences(base, needle, counted),
+            (IDS::VariantOf { base }, _) if self.lenient_variation_indicators => self.count_occurrences(base, needle, counted),
+            _ => 0,
+        }
+    }
+
+    /// The entries containing the component `needle`, like `search_find`
+    /// with that single needle, but answered from the reverse index by
+    /// walking up from the component instead of testing every entry.
+    pub fn find_leaf_only(&self, needle: char) -> Vec<(char, Tag)> {
+        let mut containers: Vec<char> = if self.fuzzy_components {
+            self.parents.keys().copied().filter(|&c| self.chars_equal(c, needle)).collect()
+        } else {
+            vec![needle]
+        };
+        let mut seen: Set<char> = Set::new();
+        let mut result: Set<(char, TagId)> = Set::new();
+        while let Some(c) = containers.pop() {
+            if !seen.insert(c) {
+                continue;
+            }
+            for key in self.parents.get(&c).into_iter().flatten() {
+                if !self.lenient_variation_indicators && self.variant_entries.contains(key) && !self.entry_ids(key).is_some_and(|ids| ids.plain_leaves().contains(&c)) {
+                    continue;
+                }
+                if !result.insert(*key) {
+                    continue;
+                }
+                // A character only passes the component on when expanded
+                if self.entry_ids(key) != Some(&IDS::Char(key.0)) && !seen.contains(&key.0) {
+                    containers.push(key.0);
+                }
+            }
+        }
+        let mut result: Vec<(char, Tag)> = result.into_iter().map(|key| self.entry_key(key)).collect();
+        result.sort();
+        result
+    }
+
+    /// The child-index path of the narrowest subtree of `haystack` that
+    /// still contains `needle`, or None if it does not contain it at all.
+    pub fn subcomponent_path(&self, haystack: &IDS, needle: &IDS) -> Option<Vec<usize>> {
+        narrowest_path(haystack, 0, &|ids, _| self.ids_has_subcomponent(ids, needle))
+    }
+
+    /// Like `subcomponent_path`, but for a pattern as used by `pmatch`.
+    pub fn matching_subcomponent_path(&self, haystack: &IDS, pattern: &IDS, wildcard_k: char) -> Option<Vec<usize>> {
+        self.matching_subcomponent_path_at_depth(haystack, pattern, wildcard_k, None)
+    }
+
+    /// Like `matching_subcomponent_path`, but for a match at `depth` as by
+    /// `ids_has_matching_subcomponent_at_depth`.
+    pub fn matching_subcomponent_path_at_depth(&self, haystack: &IDS, pattern: &IDS, wildcard_k: char, depth: Option<usize>) -> Option<Vec<usize>> {
+        narrowest_path(haystack, 0, &|ids, level| match depth {
+            None => self.ids_has_matching_subcomponent(ids, pattern, wildcard_k),
+            Some(d) => d >= level && self.ids_has_matching_subcomponent_at_depth(ids, pattern, wildcard_k, Some(d - level)),
+        })
+    }
+
+    pub fn char_has_subcomponent(&self, k: char, needle: &IDS) -> bool {
+        let ids = IDS::Char(k);
+        self.ids_has_subcomponent(&ids, needle)
+    }
+
+    /// Like a `find` search, but only considers the variants of `chars`
+    /// instead of scanning the whole table.
+    pub fn search_find_within(&self, chars: &[char], needles: &[IDS]) -> Vec<(char, Tag)> {
+        let mut result = vec![];
+        for k in chars {
+            let Some(k_tags) = self.tags.get(k) else {
+                continue;
+            };
+            for k_tag in k_tags {
+                let Some(ids) = self.entry_ids(&(*k, *k_tag)) else {
+                    continue;
+                };
+                if needles.iter().all(|needle| self.ids_has_subcomponent(ids, needle)) {
+                    result.push(self.entry_key((*k, *k_tag)));
+                }
+            }
+        }
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// Whether `k` has an entry of its own in the table.
+    pub fn contains_char(&self, k: char) -> bool {
+        self.tags.contains_key(&k)
+    }
+
+    pub fn decomposition(&self, k: char, tag: &Tag) -> Option<&IDS> {
+        self.entry_ids(&(k, self.tag_pool.id(tag)?))
+    }
+
+    /// The decomposition of the first named (non-anon) variant of `k`,
+    /// preferring variants of the preferred region if one is set.
+    pub fn primary_decomposition(&self, k: char) -> Option<&IDS> {
+        self.entry_ids(&(k, self.primary_tag_id(k)?))
+    }
+
+    /// Each character of `text` with its primary decomposition, or None
+    /// for characters not in the table such as punctuation. Whitespace,
+    /// combining marks and variation selectors are left out.
+    pub fn annotate(&self, text: &str) -> Vec<(char, Option<&IDS>)> {
+        text.chars()
+            .filter(|c| !c.is_whitespace() && !is_combining_mark(*c) && !is_variation_selector(*c))
+            .map(|c| (c, self.primary_decomposition(c)))
+            .collect()
+    }
+
+    /// The largest part `a` and `b` share, looking into the primary
+    /// decompositions of both and of their components. Size counts the
+    /// nodes a part expands to, so a shared character wins over its own
+    /// decomposition. None if they share nothing.
+    pub fn common_substructure(&self, a: char, b: char) -> Option<IDS> {
+        let mut parts_a = Set::new();
+        self.collect_parts(&IDS::Char(a), MAX_EXPANSION_DEPTH, &mut parts_a);
+        let mut parts_b = Set::new();
+        self.collect_parts(&IDS::Char(b), MAX_EXPANSION_DEPTH, &mut parts_b);
+        let mut shared: Vec<IDS> = parts_a.into_iter().filter(|p| parts_b.contains(p)).collect();
+        shared.sort_by_cached_key(|p| (core::cmp::Reverse(self.expanded_size(p, MAX_EXPANSION_DEPTH)), p.node_count(), p.clone()));
+        shared.into_iter().next()
+    }
+
+    fn collect_parts(&self, ids: &IDS, depth: usize, parts: &mut Set<IDS>) {
+        if !parts.insert(ids.clone()) {
+            return;
+        }
+        match ids {
+            IDS::Composition { children, .. } => {
+                for c in children {
+                    self.collect_parts(c, depth, parts);
+                }
+            }
+            IDS::Char(k) if depth > 0 => {
+                if let Some(d) = self.primary_decomposition(*k).filter(|d| *d != ids) {
+                    self.collect_parts(d, depth - 1, parts);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn expanded_size(&self, ids: &IDS, depth: usize) -> usize {
+        match ids {
+            IDS::Composition { children, .. } => 1 + children.iter().map(|c| self.expanded_size(c, depth)).sum::<usize>(),
+            IDS::Char(k) if depth > 0 => match self.primary_decomposition(*k).filter(|d| *d != ids) {
+                Some(d) => self.expanded_size(d, depth - 1),
+                None => 1,
+            },
+            _ => 1,
+        }
+    }
+
+    /// The tag of the variant `primary_decomposition` picks.
+    pub fn primary_tag(&self, k: char) -> Option<&Tag> {
+        Some(self.tag(self.primary_tag_id(k)?))
+    }
+
+    fn primary_tag_id(&self, k: char) -> Option<TagId> {
+        let k_tags = self.variant_tags(k);
+        // an entry of a lazy table that does not parse is only found out here
+        k_tags.iter()
+            .filter(|t| self.entry_ids(&(k, **t)).is_some())
+            .find(|t| !matches!(self.tag(**t), Tag::Anon(_)))
+            .or_else(|| k_tags.first())
+            .copied()
+    }
+
+    /// The left part of `k`: the first child of its primary decomposition
+    /// when that is a horizontal composition such as ⿰ or ⿲.
+    pub fn left_of(&self, k: char) -> Option<IDS> {
+        self.part_of(k, Direction::Hort, false)
+    }
+
+    /// The right part of `k`: the last child of a horizontal composition.
+    pub fn right_of(&self, k: char) -> Option<IDS> {
+        self.part_of(k, Direction::Hort, true)
+    }
+
+    /// The top part of `k`: the first child of a vertical composition.
+    pub fn top_of(&self, k: char) -> Option<IDS> {
+        self.part_of(k, Direction::Vert, false)
+    }
+
+    /// The bottom part of `k`: the last child of a vertical composition.
+    pub fn bottom_of(&self, k: char) -> Option<IDS> {
+        self.part_of(k, Direction::Vert, true)
+    }
+
+    fn part_of(&self, k: char, direction: Direction, last: bool) -> Option<IDS> {
+        match self.primary_decomposition(k)? {
+            IDS::Composition { idc, children } if idc.direction() == direction => {
+                if last { children.last() } else { children.first() }.cloned()
+            }
+            _ => None,
+        }
+    }
+
+    /// The characters whose primary decomposition has the same top-level
+    /// IDC as that of `k` and differs from it in exactly one child, such
+    /// as 請 and 晴 for 清. Children are compared as by `ids_match`, so
+    /// writing a child out instead of as a character is no difference.
+    pub fn minimal_pairs(&self, k: char) -> Vec<char> {
+        let Some(IDS::Composition { children, .. }) = self.primary_decomposition(k) else {
+            return vec![];
+        };
+        // differing in exactly one child, each is an analog at one position
+        let mut pairs: Vec<char> = (0..children.len())
+            .flat_map(|position| self.structural_analogs(k, position))
+            .collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// The characters built like `template` with the child at
+    /// `vary_position` replaced: their primary decomposition has the same
+    /// top-level IDC, matches every other child as by `ids_match`, and
+    /// differs in that one, so for 清 and position 0 these are 請 and 晴.
+    /// Empty when the template is atomic or has no such child.
+    pub fn structural_analogs(&self, template: char, vary_position: usize) -> Vec<char> {
+        let Some(IDS::Composition { idc, children }) = self.primary_decomposition(template) else {
+            return vec![];
+        };
+        if vary_position >= children.len() {
+            return vec![];
+        }
+        let mut analogs: Vec<char> = self.tags.keys().copied()
+            .filter(|&other| other != template)
+            .filter(|&other| match self.primary_decomposition(other) {
+                Some(IDS::Composition { idc: other_idc, children: other_children }) if other_idc == idc => {
+                    children.iter().zip(other_children).enumerate()
+                        .all(|(i, (a, b))| self.ids_match(a, b, NO_WILDCARD) != (i == vary_position))
+                }
+                _ => false,
+            })
+            .collect();
+        analogs.sort_unstable();
+        analogs
+    }
+
+    /// Whether the primary decomposition of `smaller` is a leading part of
+    /// that of `larger`. For ⿰⿲ and ⿱⿳ both are read as the list of parts
+    /// running in that direction, however they are nested, so ⿱口口 is a
+    /// prefix of ⿳口口一 and of ⿱⿱口口一; other IDCs must be equal. Each
+    /// part of `smaller` has to match the part of `larger` at the same
+    /// place, expanding components as `ids_match` does. As with
+    /// `str::starts_with`, a character is a prefix of any character with
+    /// the same structure, itself included; atomic characters are a prefix
+    /// of nothing.
+    pub fn is_structural_prefix(&self, smaller: char, larger: char) -> bool {
+        let (Some(a @ IDS::Composition { idc: ac, children: xs }), Some(b @ IDS::Composition { idc: bc, children: ys })) =
+            (self.primary_decomposition(smaller), self.primary_decomposition(larger)) else {
+            return false;
+        };
+        if ac != bc && (!ac.direction().is_linear() || !ac.is_same_direction(*bc)) {
+            return false;
+        }
+        let (prefix, parts) = match ac.direction() {
+            dir if dir.is_linear() => (flatten_direction(a, dir), flatten_direction(b, dir)),
+            _ => (xs.iter().collect(), ys.iter().collect()),
+        };
+        prefix.len() <= parts.len()
+            && prefix.iter().zip(parts.iter()).all(|(p, q)| self.ids_match(q, p, NO_WILDCARD))
+    }
+
+    /// Whether `needle` is one of the top-level parts of the primary
+    /// decomposition of `k`, as written: nothing is expanded.
+    pub fn has_direct_component(&self, k: char, needle: &IDS) -> bool {
+        let Some(IDS::Composition { children, .. }) = self.primary_decomposition(k) else {
+            return false;
+        };
+        children.iter().any(|child| match (child, needle) {
+            (IDS::Char(a), IDS::Char(b)) => self.chars_equal(*a, *b),
+            _ => child == needle,
+        })
+    }
+
+    /// The characters at most `radius` steps from `k` in the component
+    /// graph, where each step goes from a character to a component of one
+    /// of its decompositions or to a character using it. `k` itself is not
+    /// included.
+    pub fn neighbors(&self, k: char, radius: usize) -> Set<char> {
+        let mut seen: Set<char> = Set::new();
+        seen.insert(k);
+        let mut frontier = vec![k];
+        for _ in 0..radius {
+            let mut next = vec![];
+            for c in frontier {
+                let children = self.tags.get(&c).into_iter().flatten()
+                    .filter_map(|tag| self.entry_ids(&(c, *tag)))
+                    .flat_map(|ids| ids.leaves());
+                let parents = self.parents.get(&c).into_iter().flatten().map(|(p, _)| *p);
+                for n in children.chain(parents) {
+                    if seen.insert(n) {
+                        next.push(n);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        seen.remove(&k);
+        seen
+    }
+
+    /// Drops every character except `keep` and the components their
+    /// decompositions (of any variant) transitively refer to.
+    pub fn retain_reachable(&mut self, keep: &[char]) {
+        self.load_all();
+        let mut reachable: Set<char> = Set::new();
+        let mut pending: Vec<char> = keep.to_vec();
+        while let Some(k) = pending.pop() {
+            if !reachable.insert(k) {
+                continue;
+            }
+            for tag in self.tags.get(&k).into_iter().flatten() {
+                if let Some(ids) = self.entry_ids(&(k, *tag)) {
+                    pending.extend(ids.leaves().into_iter().filter(|c| !reachable.contains(c)));
+                }
+            }
+        }
+        self.table.retain(|(k, _), _| reachable.contains(k));
+        self.tags.retain(|k, _| reachable.contains(k));
+        self.rebuild_index();
+    }
+
+    /// Replaces every subtree equal to `target` in every decomposition by
+    /// `replacement`. Returns the number of subtrees replaced.
+    pub fn replace_everywhere(&mut self, target: &IDS, replacement: &IDS) -> usize {
+        self.load_all();
+        let mut count = 0;
+        for ids in self.table.values_mut().filter_map(|slot| slot.get_mut()?.as_mut()) {
+            let before = count;
+            let replaced = ids.replace_counting(target, replacement, &mut count);
+            if count > before {
+                *ids = replaced;
+            }
+        }
+        if count > 0 {
+            self.rebuild_index();
+        }
+        count
+    }
+
+    /// Releases the spare capacity of the internal maps, e.g. after
+    /// `retain_reachable`.
+    pub fn shrink_to_fit(&mut self) {
+        self.load_all();
+        #[cfg(feature = "std")]
+        {
+            self.table.shrink_to_fit();
+            self.tags.shrink_to_fit();
+            self.parents.shrink_to_fit();
+            self.closures.shrink_to_fit();
+            self.strokes.shrink_to_fit();
+            self.frequencies.shrink_to_fit();
+        }
+        self.tags.values_mut().for_each(Vec::shrink_to_fit);
+        self.parents.values_mut().for_each(Vec::shrink_to_fit);
+    }
+
+    /// A rough estimate of the memory the entries and their indexes take,
+    /// counting allocated capacity but not the allocator's own overhead.
+    pub fn approximate_memory_bytes(&self) -> usize {
+        use core::mem::size_of;
+        // entries of a lazy table not used yet count without their decomposition
+        let table = slots(&self.table) * (size_of::<(char, TagId)>() + size_of::<Slot>())
+            + self.table.values().filter_map(loaded).map(ids_heap_bytes).sum::<usize>()
+            + self.lazy.as_ref().map_or(0, |lazy| slots(&lazy.fields) * size_of::<((char, TagId), (usize, Range<usize>))>());
+        let tags = slots(&self.tags) * (size_of::<char>() + size_of::<Vec<TagId>>())
+            + self.tags.values().map(|v| v.capacity() * size_of::<TagId>()).sum::<usize>();
+        // each tag is held by the pool's list and by its map
+        let tag_pool = self.tag_pool.tags.capacity() * size_of::<Tag>() + slots(&self.tag_pool.ids) * size_of::<(Tag, TagId)>()
+            + 2 * self.tag_pool.tags.iter().map(tag_heap_bytes).sum::<usize>();
+        let parents = slots(&self.parents) * (size_of::<char>() + size_of::<Vec<(char, TagId)>>())
+            + self.parents.values().map(|v| v.capacity() * size_of::<(char, TagId)>()).sum::<usize>();
+        let closures = slots(&self.closures) * (size_of::<char>() + size_of::<Set<char>>())
+            + self.closures.values().map(|c| c.len() * size_of::<char>()).sum::<usize>();
+        let numbers = slots(&self.strokes) * size_of::<(char, u32)>() + slots(&self.frequencies) * size_of::<(char, u64)>();
+        table + tags + tag_pool + parents + closures + numbers
+    }
+
+    /// Folds region variants of a character that decompose identically
+    /// into one variant carrying all their regions, e.g. ⿰氵青[G], [T] and
+    /// [J] into ⿰氵青[GTJ]. Untagged and anonymous variants are left alone.
+    /// Returns the number of entries removed.
+    pub fn merge_variants_by_region(&mut self) -> usize {
+        self.load_all();
+        let mut removed = 0;
+        let chars: Vec<char> = self.tags.keys().copied().collect();
+        for k in chars {
+            let variants = self.variants(k);
+            let mut merged: Vec<(Tag, IDS)> = vec![];
+            for (tag, ids) in &variants {
+                let same = merged.iter().position(|(t, i)| !t.is_untagged() && !tag.is_untagged() && i == ids);
+                let combined = same.and_then(|i| match (&merged[i].0, tag) {
+                    (Tag::Variant(regions), Tag::Variant(more)) => {
+                        let mut regions = regions.clone();
+                        for r in more {
+                            if !regions.contains(r) {
+                                regions.push(r.clone());
+                            }
+                        }
+                        Some((i, Tag::Variant(regions)))
+                    }
+                    _ => None,
+                });
+                // a tag another variant already has would overwrite it
+                let taken = |combined: &Tag, i: usize| {
+                    variants.iter().any(|(t, other)| t == combined && other != ids)
+                        || merged.iter().enumerate().any(|(j, (t, _))| j != i && t == combined)
+                };
+                match combined {
+                    Some((i, combined)) if !taken(&combined, i) => {
+                        merged[i].0 = combined;
+                        removed += 1;
+                    }
+                    _ => merged.push((tag.clone(), ids.clone())),
+                }
+            }
+            if merged.len() == self.tags[&k].len() {
+                continue;
+            }
+            for tag in &self.tags[&k] {
+                self.table.remove(&(k, *tag));
+            }
+            let merged: Vec<(TagId, IDS)> = merged.into_iter().map(|(t, ids)| (self.tag_pool.intern(t), ids)).collect();
+            self.tags.insert(k, merged.iter().map(|(t, _)| *t).collect());
+            for (tag, ids) in merged {
+                self.table.insert((k, tag), Slot::from(Some(ids)));
+            }
+        }
+        if removed > 0 {
+            self.rebuild_index();
+        }
+        removed
+    }
+
+    /// Removes the variants of a character that decompose it to itself,
+    /// such as `亅 亅`, when it also has a real decomposition. A character
+    /// whose only variant is trivial keeps it, as that is how atomic
+    /// components are written. Returns the number of entries removed.
+    pub fn prune_trivial(&mut self) -> usize {
+        self.load_all();
+        let mut removed = 0;
+        for (k, k_tags) in self.tags.iter_mut() {
+            let trivial = |tag: &TagId| self.table.get(&(*k, *tag)).and_then(loaded) == Some(&IDS::Char(*k));
+            if k_tags.iter().all(trivial) {
+                continue;
+            }
+            let (pruned, kept): (Vec<TagId>, Vec<TagId>) = k_tags.iter().partition(|tag| trivial(tag));
+            for tag in &pruned {
+                self.table.remove(&(*k, *tag));
+            }
+            removed += pruned.len();
+            *k_tags = kept;
+        }
+        if removed > 0 {
+            self.rebuild_index();
+        }
+        removed
+    }
+
+    /// Every IDC that occurs somewhere in the loaded decompositions.
+    pub fn used_idcs(&self) -> BTreeSet<IDC> {
+        let mut idcs = BTreeSet::new();
+        for node in self.entries().flat_map(|(_, ids)| ids.iter_nodes()) {
+            if let IDS::Composition { idc, .. } = node {
+                idcs.insert(*idc);
+            }
+        }
+        idcs
+    }
+
+    pub fn stats(&self) -> TableStats {
+        let mut stats = TableStats {
+            distinct_chars: self.tags.len(),
+            ..Default::default()
+        };
+        for (_, ids) in self.entries() {
+            stats.total_entries += 1;
+            stats.max_depth = stats.max_depth.max(ids.depth());
+        }
+        for (k, k_tags) in &self.tags {
+            if k_tags.len() > 1 {
+                stats.characters_with_multiple_variants += 1;
+            }
+            if !matches!(self.primary_decomposition(*k), Some(IDS::Composition { .. })) {
+                stats.atomic_chars += 1;
+            }
+        }
+        stats
+    }
+
+    /// What changed from `self` to `other`. Tagged variants are compared
+    /// by tag; untagged and anonymous ones only by their decompositions,
+    /// since which one ends up anonymous depends on the order of the lines.
+    pub fn diff(&self, other: &IDSTable) -> TableDiff {
+        let mut diff = TableDiff::default();
+        let mut chars: Vec<char> = self.tags.keys().chain(other.tags.keys()).copied().collect();
+        chars.sort_unstable();
+        chars.dedup();
+        for k in chars {
+            let (old, new) = (self.variants(k), other.variants(k));
+            if new.is_empty() {
+                diff.removed.push(k);
+                continue;
+            }
+            if old.is_empty() {
+                diff.added.push(k);
+                continue;
+            }
+            let mut change = DecompositionChange { character: k, removed: vec![], added: vec![] };
+            let mut unmatched_new: Vec<&(Tag, IDS)> = new.iter().collect();
+            for entry in &old {
+                let (tag, ids) = entry;
+                let found = unmatched_new.iter().position(|(t, i)| i == ids && (t == tag || (t.is_untagged() && tag.is_untagged())));
+                match found {
+                    Some(i) => {
+                        unmatched_new.swap_remove(i);
+                    }
+                    None => change.removed.push(entry.clone()),
+                }
+            }
+            change.added = new.iter().filter(|e| unmatched_new.contains(e)).cloned().collect();
+            if !change.removed.is_empty() || !change.added.is_empty() {
+                diff.changed.push(change);
+            }
+        }
+        diff
+    }
+
+    /// A label for the variant `tag` of `k` that tells it apart from the
+    /// other variants of `k`: its regions if it has any, otherwise
+    /// `variant N` with its place in load order. An untagged variant that
+    /// is the only one without regions gets an empty label.
+    pub fn tag_for_display(&self, k: char, tag: &Tag) -> String {
+        if !tag.is_untagged() {
+            // without the comma `Display` keeps on a lone code such as `KP,`
+            return match tag.regions() {
+                [code] => code.clone(),
+                _ => tag.to_string(),
+            };
+        }
+        let k_tags = self.tags.get(&k).map_or(&[][..], Vec::as_slice);
+        let unnamed = k_tags.iter().filter(|t| self.tag(**t).is_untagged()).count();
+        match (tag, k_tags.iter().position(|t| self.tag(*t) == tag)) {
+            (Tag::Variant(_), _) if unnamed <= 1 => String::new(),
+            (_, Some(i)) => format!("variant {}", i + 1),
+            (Tag::Anon(n), None) => format!("variant {}", n + 1),
+            (Tag::Variant(_), None) => String::new(),
+        }
+    }
+
+    /// All `(tag, decomposition)` pairs of `k`, in load order.
+    pub fn variants(&self, k: char) -> Vec<(Tag, IDS)> {
+        self.tags.get(&k).into_iter().flatten()
+            .filter_map(|tag| self.entry_ids(&(k, *tag)).map(|ids| (self.tag(*tag).clone(), ids.clone())))
+            .collect()
+    }
+
+    /// Narrows the result of a previous `find` by one more needle. Adding a
+    /// needle can only shrink the result, so there is no need to rescan the
+    /// whole table.
+    pub fn refine(&self, previous_results: &[(char, Tag)], additional_needle: &IDS) -> Vec<(char, Tag)> {
+        previous_results.iter()
+            .filter(|(k, t)| {
+                self.decomposition(*k, t)
+                    .is_some_and(|ids| self.ids_has_subcomponent(ids, additional_needle))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The number of entries, counting every variant of a character.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((char, &Tag), &IDS)> {
+        self.entries().map(|((k, id), ids)| ((*k, self.tag(*id)), ids))
+    }
+
+    /// Calls `f` with every entry, in the order of `iter`, until it
+    /// breaks. Returns whether it did.
+    pub fn for_each<F: FnMut(char, &Tag, &IDS) -> ControlFlow<()>>(&self, mut f: F) -> ControlFlow<()> {
+        for ((k, id), ids) in self.entries() {
+            f(*k, self.tag(*id), ids)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Maps the top-level child count of each character's primary
+    /// decomposition (0 for atomic) to the number of characters with it.
+    pub fn glyph_count_histogram(&self) -> Map<usize, usize> {
+        let mut histogram = Map::new();
+        for k in self.tags.keys() {
+            let Some(ids) = self.primary_decomposition(*k) else {
+                continue;
+            };
+            let arity = match ids {
+                IDS::Composition { children, .. } => children.len(),
+                _ => 0,
+            };
+            *histogram.entry(arity).or_insert(0) += 1;
+        }
+        histogram
+    }
+}
+
+/// The character named by a `U+XXXX` column.
+fn parse_codepoint(s: &str) -> Option<char> {
+    let hex = s.strip_prefix("U+").or_else(|| s.strip_prefix("u+"))?;
+    char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+}
+
+fn parse_char_numbers(content: &str) -> Vec<(char, u64)> {
+    let mut result = vec![];
+    for line in content.lines() {
+        let parts: Vec<&str> = line.split_whitespace().filter(|p| !p.starts_with("U+")).collect();
+        let [k, n] = parts[..] else {
+            continue;
+        };
+        let (Some(k), Ok(n)) = (k.chars().next(), n.parse()) else {
+            warn!("Malformed line {}", line);
+            continue;
+        };
+        result.push((k, n));
+    }
+    result
+}
+
+// Entries a map has room for: its capacity where it has one
+#[cfg(feature = "std")]
+fn slots<K, V>(map: &Map<K, V>) -> usize {
+    map.capacity()
+}
+
+#[cfg(not(feature = "std"))]
+fn slots<K, V>(map: &Map<K, V>) -> usize {
+    map.len()
+}
+
+fn tag_heap_bytes(tag: &Tag) -> usize {
+    match tag {
+        Tag::Variant(regions) => regions.capacity() * core::mem::size_of::<String>() + regions.iter().map(String::capacity).sum::<usize>(),
+        Tag::Anon(_) => 0,
+    }
+}
+
+fn ids_heap_bytes(ids: &IDS) -> usize {
+    match ids {
+        IDS::Special(s) | IDS::Entity(s) => s.capacity(),
+        IDS::OneOf(cs) => cs.capacity() * core::mem::size_of::<char>(),
+        IDS::Modified { base, .. } | IDS::VariantOf { base } => core::mem::size_of::<IDS>() + ids_heap_bytes(base),
+        IDS::Composition { children, .. } => {
+            children.capacity() * core::mem::size_of::<IDS>() + children.iter().map(ids_heap_bytes).sum::<usize>()
+        }
+        IDS::Char(_) | IDS::CharWithVS { .. } => 0,
+    }
+}
+
+// The children of nested compositions running in direction `dir`, e.g. the
+// four parts of ⿱⿱ab⿱cd for Vert.
+fn flatten_direction(ids: &IDS, dir: Direction) -> Vec<&IDS> {
+    match ids {
+        IDS::Composition { idc, children } if idc.direction() == dir && dir.is_linear() => {
+            children.iter().flat_map(|c| flatten_direction(c, dir)).collect()
+        }
+        _ => vec![ids],
+    }
+}
+
+fn ends_with_ellipsis(children: &[IDS]) -> bool {
+    children.last() == Some(&IDS::Char(ELLIPSIS_CHAR))
+}
+
+// The decomposition in `slot` if it has been parsed
+fn loaded(slot: &Slot) -> Option<&IDS> {
+    slot.get()?.as_ref()
+}
+
+// The character of a data line and its non-empty decomposition fields, or
+// None for a blank or malformed line
+fn line_fields<'a>(line_no: usize, line: &'a str, options: &LoadOptions, issues: &mut Vec<ValidationIssue>) -> Option<(char, Vec<&'a str>)> {
+    let parts: Vec<&str> = match options.delimiter {
+        Some(delimiter) => line.split(delimiter).map(str::trim).collect(),
+        None => line.split_whitespace().collect(),
+    };
+    if parts.iter().all(|p| p.is_empty()) {
+        return None;
+    }
+    if parts.len() <= options.char_column.max(options.ids_start_column) {
+        warn!("Malformed line {}", line);
+        issues.push(ValidationIssue::Malformed { line: line_no });
+        return None;
+    }
+    let char = parts[options.char_column].chars().next()?;
+    if let Some(codepoint) = options.codepoint_column.and_then(|c| parts.get(c)) {
+        if parse_codepoint(codepoint) != Some(char) {
+            warn!("Code point {} does not match {} on line {}", codepoint, char, line_no);
+            issues.push(ValidationIssue::CodepointMismatch { line: line_no, character: char, codepoint: codepoint.to_string() });
+        }
+    }
+    let fields = (options.ids_start_column..parts.len())
+        .filter(|&i| i != options.char_column && Some(i) != options.codepoint_column)
+        .map(|i| parts[i])
+        .filter(|p| !p.is_empty())
+        .collect();
+    Some((char, fields))
+}
+
+// `contains` is also given how many levels below the root `ids` is
+fn narrowest_path(ids: &IDS, level: usize, contains: &impl Fn(&IDS, usize) -> bool) -> Option<Vec<usize>> {
+    if !contains(ids, level) {
+        return None;
+    }
+    if let IDS::Composition { children, .. } = ids {
+        for (i, c) in children.iter().enumerate() {
+            if let Some(mut path) = narrowest_path(c, level + 1, contains) {
+                path.insert(0, i);
+                return Some(path);
+            }
+        }
+    }
+    Some(vec![])
+}
+
+fn parser_tag(input: &str) -> IResult<&str, String> {
+    delimited(
+        char('['),
+        take_while1(|c| c != ']'),
+        char(']'),
+    )
+        .map(String::from)
+        .parse(input)
+}
+
+fn parser_special(input: &str) -> IResult<&str, IDS> {
+    delimited(
+        satisfy(|c| c == '{'),
+        take_while1(|c| c != '}'),
+        satisfy(|c| c == '}')
+    )
+        .map(|s: &str| IDS::Special(s.to_string()))
+        .parse(input)
+}
+
+fn parser_entity(input: &str) -> IResult<&str, IDS> {
+    delimited(
+        char('&'),
+        take_while1(|c: char| c.is_ascii_alphanumeric() || "-_+".contains(c)),
+        char(';'),
+    )
+        .map(|s: &str| IDS::Entity(s.to_string()))
+        .parse(input)
+}
+
+// Only tried where a component is expected, so a trailing `[...]` after a
+// complete IDS is still read as its tag.
+fn parser_class(input: &str) -> IResult<&str, IDS> {
+    delimited(
+        satisfy(|c| c == '['),
+        take_while1(|c| c != ']' && !is_idc(c)),
+        satisfy(|c| c == ']')
+    )
+        .map(|s: &str| IDS::OneOf(s.chars().collect()))
+        .parse(input)
+}
+
+fn is_variation_selector(c: char) -> bool {
+    matches!(c, '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
+// The common combining diacritic blocks, enough to skip accents and
+// enclosing marks in running text
+fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036F}' | '\u{1AB0}'..='\u{1AFF}' | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}' | '\u{3099}'..='\u{309A}' | '\u{FE20}'..='\u{FE2F}')
+}
+
+/// Trailing modifiers some IDS dialects use: mirrored horizontally (↔) or
+/// vertically (↕), rotated clockwise (↷) or counterclockwise (↶).
+pub const MODIFIER_CHARS: &str = "↔↕↷↶";
+
+/// The ideographic variation indicator, written before a component that
+/// stands for some unrepresentable variant of it.
+pub const VARIATION_INDICATOR: char = '〾';
+
+fn is_modifier(c: char) -> bool {
+    MODIFIER_CHARS.contains(c)
+}
+
+fn with_modifier((ids, modifier): (IDS, Option<char>)) -> IDS {
+    match modifier {
+        Some(modifier) => IDS::Modified { base: Box::new(ids), modifier },
+        None => ids,
+    }
+}
+
+fn parser_char(input: &str) -> IResult<&str, IDS> {
+    (satisfy(|c| !is_idc(c) && !is_variation_selector(c) && !is_modifier(c) && c != VARIATION_INDICATOR && !"{[".contains(c)), opt(satisfy(is_variation_selector)))
+        .map(|(base, vs)| match vs {
+            Some(vs) => IDS::CharWithVS { base, vs },
+            None => IDS::Char(base),
+        })
+        .parse(input)
+}
+
+fn parser_composition(input: &str) -> IResult<&str, IDS> {
+    let start = input;
+    let (input, idc_char) = satisfy(is_idc).parse(input)?;
+    let arity = idc_arity(idc_char);
+    let (rest, children) = many_m_n(arity, arity, parser_ids).parse(input).map_err(|e| match e {
+        // running out of input is reported at the IDC that wanted more
+        nom::Err::Error(inner) if inner.input.is_empty() => nom::Err::Failure(nom::error::Error::new(start, nom::error::ErrorKind::Count)),
+        // nothing else starts with an IDC, so the error of the child stands
+        // instead of that of the last alternative tried at the IDC
+        nom::Err::Error(inner) => nom::Err::Failure(inner),
+        e => e,
+    })?;
+    // many_m_n has already checked the arity
+    Ok((rest, IDS::Composition {
+        idc: IDC::new(idc_char).unwrap(),
+        children,
+    }))
+}
+
+// Trailing children may be left out; the composition then has fewer
+// children than its IDC's arity.
+fn parser_partial_composition(input: &str) -> IResult<&str, IDS> {
+    let (input, idc_char) = satisfy(is_idc).parse(input)?;
+    let arity = idc_arity(idc_char);
+    let (input, children) = many_m_n(0, arity, parser_partial_ids).parse(input)?;
+    Ok((input, IDS::Composition {
+        idc: IDC::new(idc_char).unwrap(),
+        children,
+    }))
+}
+
+fn parser_partial_variant_of(input: &str) -> IResult<&str, IDS> {
+    preceded(char(VARIATION_INDICATOR), parser_partial_ids)
+        .map(|base| IDS::VariantOf { base: Box::new(base) })
+        .parse(input)
+}
+
+fn parser_partial_ids(input: &str) -> IResult<&str, IDS> {
+    (alt((parser_partial_composition, parser_partial_variant_of, parser_special, parser_entity, parser_class, parser_char)), opt(satisfy(is_modifier)))
+        .map(with_modifier)
+        .parse(input)
+}
+
+fn parser_variant_of(input: &str) -> IResult<&str, IDS> {
+    preceded(char(VARIATION_INDICATOR), parser_ids)
+        .map(|base| IDS::VariantOf { base: Box::new(base) })
+        .parse(input)
+}
+
+fn parser_ids(input: &str) -> IResult<&str, IDS> {
+    (alt((parser_composition, parser_variant_of, parser_special, parser_entity, parser_class, parser_char)), opt(satisfy(is_modifier)))
+        .map(with_modifier)
+        .parse(input)
+}
+
+fn parser_tagged_ids(input: &str) -> IResult<&str, TaggedIDS> {
+    (parser_ids, opt(parser_tag), eof)
+        .map(|(ids, tag, _)| TaggedIDS {
+            ids,
+            tag: Tag::from(tag.unwrap_or_default()),
+        })
+        .parse(input)
+}
+
+/// How `parse_with` and its siblings treat their input.
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Whether leading and trailing whitespace, including the ideographic
+    /// space U+3000 that comes along when copying from documents, is
+    /// ignored. Whitespace inside the decomposition is left alone.
+    pub trim: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions { trim: true }
+    }
+}
+
+pub fn parse(input: &str) -> Result<IDS, ParseError> {
+    parse_with(input, &ParseOptions::default())
+}
+
+pub fn parse_with(input: &str, options: &ParseOptions) -> Result<IDS, ParseError> {
+    parse_input(input, options, parser_ids)
+}
+
+// Runs `parser` over all of `input`, trimmed if asked to; positions in
+// errors still count from the start of `input`
+fn parse_input<'a, T>(input: &'a str, options: &ParseOptions, parser: impl Fn(&'a str) -> IResult<&'a str, T>) -> Result<T, ParseError> {
+    let (body, offset) = if options.trim {
+        let start = input.trim_start();
+        (start.trim_end(), position_of(input, start))
+    } else {
+        (input, 0)
+    };
+    let result = match parser(body).finish() {
+        Ok(("", parsed)) => return Ok(parsed),
+        Ok((rest, _)) => ParseError::TrailingInput { position: position_of(body, rest) },
+        Err(e) => parse_error(body, e),
+    };
+    Err(result.shifted(offset))
+}
+
+fn position_of(input: &str, rest: &str) -> usize {
+    input.chars().count() - rest.chars().count()
+}
+
+fn parse_error(input: &str, e: nom::error::Error<&str>) -> ParseError {
+    let position = position_of(input, e.input);
+    let idc = e.input.chars().next().and_then(IDC::new);
+    match (e.code, idc) {
+        (nom::error::ErrorKind::Count, Some(idc)) => {
+            // count the components that were there
+            let mut rest = &e.input[idc.0.len_utf8()..];
+            let mut found = 0;
+            while let Ok((after, _)) = parser_ids(rest) {
+                rest = after;
+                found += 1;
+            }
+            ParseError::MissingComponents { idc, position, expected: idc.arity(), found }
+        }
+        _ => ParseError::Invalid { position },
+    }
+}
+
+/// Parses a pattern in which compositions may omit trailing children, e.g.
+/// `⿰氵` for "氵 on the left, anything on the right". Omitted children act
+/// as wildcards in `ids_match`.
+pub fn parse_partial(input: &str) -> Result<IDS, ParseError> {
+    parse_partial_with(input, &ParseOptions::default())
+}
+
+pub fn parse_partial_with(input: &str, options: &ParseOptions) -> Result<IDS, ParseError> {
+    parse_input(input, options, parser_partial_ids)
+}
+
+fn parser_mixed_child(input: &str) -> IResult<&str, ChildPattern> {
+    alt((
+        preceded(char(CONTAINS_MARKER), parser_partial_ids).map(ChildPattern::Contains),
+        parser_partial_ids.map(|ids| match ids {
+            IDS::Char('.') => ChildPattern::Any,
+            ids => ChildPattern::Exact(ids),
+        }),
+    ))
+        .parse(input)
+}
+
+fn parser_mixed(input: &str) -> IResult<&str, MixedPattern> {
+    let (input, idc_char) = satisfy(is_idc).parse(input)?;
+    let arity = idc_arity(idc_char);
+    let (input, children) = many_m_n(0, arity, parser_mixed_child).parse(input)?;
+    Ok((input, MixedPattern { idc: IDC::new(idc_char).unwrap(), children }))
+}
+
+/// Reads a mixed pattern such as `⿰女*子`: an IDC followed by its
+/// children, each a partial decomposition matched as a whole, `.` for any
+/// part, or `*` and a partial decomposition the part has to contain.
+pub fn parse_mixed(input: &str) -> Result<MixedPattern, ParseError> {
+    parse_input(input, &ParseOptions::default(), parser_mixed)
+}
+
+pub fn parse_tagged(input: &str) -> Result<TaggedIDS, ParseError> {
+    parse_tagged_with(input, &ParseOptions::default())
+}
+
+pub fn parse_tagged_with(input: &str, options: &ParseOptions) -> Result<TaggedIDS, ParseError> {
+    parse_input(input, options, parser_tagged_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(data: &str) -> IDSTable {
+        IDSTable::load_from_string(data).unwrap()
+    }
+
+    #[test]
+    fn glyph_count_histogram_uses_primary_variant() {
+        let t = table("U+53E3 口 口\nU+660E 明 ⿰日月\nU+5668 器 ⿳吅犬吅\nU+9752 青 ⿱龶⺝[G] ⿳龶冂二[T]");
+        let histogram = t.glyph_count_histogram();
+        assert_eq!(histogram.get(&0), Some(&1));
+        assert_eq!(histogram.get(&2), Some(&2));
+        assert_eq!(histogram.get(&3), Some(&1));
+    }
+
+    #[test]
+    fn search_find_within_only_scans_given_chars() {
+        let t = table("U+6E05 清 ⿰氵青\nU+6674 晴 ⿰日青\nU+8ACB 請 ⿰言青\nU+6CB3 河 ⿰氵可");
+        let needles = vec![IDS::Char('青')];
+        let result = t.search_find_within(&['請', '清', '河', '〇'], &needles);
+        let chars: Vec<char> = result.iter().map(|(c, _)| *c).collect();
+        assert_eq!(chars, vec!['清', '請']);
+    }
+
+    #[test]
+    fn ids_leaves_and_specials() {
+        let ids = parse("⿰亻⿱{柬中}⿰日{⻞下}").unwrap();
+        assert_eq!(ids.leaves(), vec!['亻', '日']);
+        assert_eq!(ids.specials(), vec!["柬中", "⻞下"]);
+        assert_eq!(IDS::Char('口').leaves(), vec!['口']);
+        assert!(IDS::Char('口').specials().is_empty());
+    }
+
+    #[test]
+    fn parse_tag_region_letters() {
+        let tids = parse_tagged("⿰氵青[GTKV]").unwrap();
+        assert_eq!(tids.tag.regions(), ["G", "T", "K", "V"]);
+        assert!(tids.tag.has_region("K"));
+        assert!(!tids.tag.has_region("J"));
+        assert_eq!(tids.tag.to_string(), "GTKV");
+
+        let tids = parse_tagged("⿰氵青[J]").unwrap();
+        assert_eq!(tids.tag, Tag::Variant(vec!["J".to_string()]));
+    }
+
+    #[test]
+    fn parse_tag_comma_separated_regions() {
+        let tids = parse_tagged("⿰氵青[G,UCS2003]").unwrap();
+        assert_eq!(tids.tag.regions(), ["G", "UCS2003"]);
+        assert_eq!(tids.tag.to_string(), "G,UCS2003");
+        assert_eq!(parse_tagged("⿰氵青[UCS2003]").unwrap().tag.regions(), ["UCS2003"]);
+        assert_eq!(parse_tagged("⿰氵青[GTJ]").unwrap().tag.regions(), ["G", "T", "J"]);
+        assert_eq!(parse_tagged("⿰氵青[KP,]").unwrap().tag.regions(), ["KP"]);
+    }
+
+    #[test]
+    fn load_keeps_region_sets_apart() {
+        let t = table("U+9752 青 ⿱龶⺝[GJKVU] ⿱龶月[TH] ⿱龶月[TH]");
+        let mut tags: Vec<Tag> = t.iter().map(|((_, tag), _)| tag.clone()).collect();
+        tags.sort();
+        assert_eq!(tags.len(), 3);
+        assert!(tags.iter().any(|tag| tag.has_region("J")));
+        assert!(tags.iter().any(|tag| matches!(tag, Tag::Anon(_))));
+    }
+
+    #[test]
+    fn decomposition_accessors() {
+        let t = table("U+9752 青 ⿱龶⺝[GJKVU] ⿱龶月[TH]\nU+6E05 清 ⿰氵青");
+        let th = Tag::from("TH".to_string());
+        assert_eq!(t.decomposition('青', &th), Some(&parse("⿱龶月").unwrap()));
+        assert_eq!(t.decomposition('青', &Tag::from("J".to_string())), None);
+        assert_eq!(t.primary_decomposition('青'), Some(&parse("⿱龶⺝").unwrap()));
+        assert_eq!(t.primary_decomposition('清'), Some(&parse("⿰氵青").unwrap()));
+        assert_eq!(t.primary_decomposition('河'), None);
+        assert!(t.contains_char('清'));
+        assert!(!t.contains_char('河'));
+        assert!(!t.contains_char('氵'));
+    }
+
+    #[test]
+    fn preferred_region_drives_expansion() {
+        let data = "U+9AA8 骨 ⿱⿵冂⿰𠃍一月[G] ⿱⿵冂⿰一𠃍月[J]\nU+6ED1 滑 ⿰氵骨";
+        let mut t = table(data);
+        let g_form = parse("⿱⿵冂⿰𠃍一月").unwrap();
+        let j_form = parse("⿱⿵冂⿰一𠃍月").unwrap();
+        assert_eq!(t.primary_decomposition('骨'), Some(&g_form));
+        let leaves = t.atomic_leaves(&IDS::Char('滑'));
+        assert_eq!(leaves[2], IDS::Char('𠃍'));
+
+        t.set_preferred_region(Some("J".to_string()));
+        assert_eq!(t.preferred_region(), Some("J"));
+        assert_eq!(t.primary_decomposition('骨'), Some(&j_form));
+        let leaves = t.atomic_leaves(&IDS::Char('滑'));
+        assert_eq!(leaves[2], IDS::Char('一'));
+
+        // unknown regions fall back to the usual order
+        t.set_preferred_region(Some("K".to_string()));
+        assert_eq!(t.primary_decomposition('骨'), Some(&g_form));
+    }
+
+    #[test]
+    fn preferred_kind_selects_decompositions() {
+        let t0 = table("U+5B5D 孝 ⿱土子[A] ⿱耂子[S]\nU+54EE 哮 ⿰口孝\nU+6559 教 ⿰孝攵");
+        assert_eq!(Tag::from("A".to_string()).kind(), Some(DecompositionKind::Apparent));
+        assert_eq!(Tag::from("GS".to_string()).kind(), Some(DecompositionKind::Structural));
+        assert_eq!(Tag::from("GT".to_string()).kind(), None);
+        let apparent = parse("⿰口⿱土子").unwrap();
+        let structural = parse("⿰口⿱耂子").unwrap();
+        assert!(t0.ids_match(&IDS::Char('哮'), &apparent, '.'));
+        assert!(t0.ids_match(&IDS::Char('哮'), &structural, '.'));
+
+        let mut t = t0.clone();
+        t.set_preferred_kind(Some(DecompositionKind::Apparent));
+        assert_eq!(t.preferred_kind(), Some(DecompositionKind::Apparent));
+        assert!(t.ids_match(&IDS::Char('哮'), &apparent, '.'));
+        assert!(!t.ids_match(&IDS::Char('哮'), &structural, '.'));
+        assert!(t.ids_has_subcomponent(&IDS::Char('教'), &IDS::Char('土')));
+        assert!(!t.ids_has_subcomponent(&IDS::Char('教'), &IDS::Char('耂')));
+        assert_eq!(t.primary_decomposition('孝'), Some(&parse("⿱土子").unwrap()));
+
+        t.precompute_closures(8);
+        t.set_preferred_kind(Some(DecompositionKind::Structural));
+        assert!(!t.ids_has_subcomponent(&IDS::Char('教'), &IDS::Char('土')));
+        assert!(!t.ids_match(&IDS::Char('哮'), &apparent, '.'));
+        assert!(t.ids_match(&IDS::Char('哮'), &structural, '.'));
+        assert_eq!(t.primary_decomposition('孝'), Some(&parse("⿱耂子").unwrap()));
+    }
+
+    #[test]
+    fn fuzzy_components_use_equivalence_sets() {
+        let mut t = table("U+672A 未 未\nU+672B 末 ⿻一木\nU+5473 味 ⿰口未\nU+6CAB 沫 ⿰氵末");
+        t.load_equivalences_from_string("未末\n己 已 巳\n");
+        assert!(!t.ids_has_subcomponent(&IDS::Char('味'), &IDS::Char('末')));
+        assert!(!t.ids_match(&IDS::Char('未'), &IDS::Char('末'), '.'));
+
+        t.set_fuzzy_components(true);
+        assert!(t.ids_has_subcomponent(&IDS::Char('味'), &IDS::Char('末')));
+        assert!(t.ids_match(&IDS::Char('未'), &IDS::Char('末'), '.'));
+        assert!(t.ids_match(&parse("⿰氵未").unwrap(), &parse("⿰氵末").unwrap(), '.'));
+        assert!(t.ids_has_matching_subcomponent(&IDS::Char('沫'), &IDS::Char('未'), '.'));
+        assert!(!t.ids_match(&IDS::Char('未'), &IDS::Char('己'), '.'));
+    }
+
+    #[test]
+    fn validate_reports_structural_problems() {
+        let t = table("U+53E3 口 口\nU+53F6 叶 ⿰口十\nU+5341 十 十 ⿻一丨\nU+5403 吃 ⿰口吃");
+        let issues = t.validate();
+        assert_eq!(issues, vec![
+            ValidationIssue::SelfDecomposition { character: '十', tag: Tag::Variant(vec![]) },
+            ValidationIssue::UnknownComponent { character: '十', tag: Tag::Anon(1), component: '一' },
+            ValidationIssue::UnknownComponent { character: '十', tag: Tag::Anon(1), component: '丨' },
+            ValidationIssue::SelfDecomposition { character: '吃', tag: Tag::Variant(vec![]) },
+        ]);
+    }
+
+    #[test]
+    fn ingest_collects_line_issues() {
+        let mut t = IDSTable::default();
+        let mut issues = vec![];
+        t.ingest_line(1, "U+53E3 口 口", &mut issues);
+        t.ingest_line(2, "U+53F6 叶", &mut issues);
+        t.ingest_line(3, "U+5403 吃 ⿰口乞乞 ⿰口乞 ⿰口乞", &mut issues);
+        assert_eq!(issues, vec![
+            ValidationIssue::Malformed { line: 2 },
+            ValidationIssue::ParseFailure { line: 3, ids: "⿰口乞乞".to_string() },
+            ValidationIssue::DuplicateKey { line: 3, character: '吃', tag: Tag::Variant(vec![]) },
+        ]);
+    }
+
+    #[test]
+    fn retain_reachable_keeps_transitive_components() {
+        let mut t = table("U+6E05 清 ⿰氵青\nU+9752 青 ⿱龶⺝[G] ⿱龶月[T]\nU+6708 月 月\nU+6C35 氵 氵\nU+6CB3 河 ⿰氵可\nU+53EF 可 ⿹丁口");
+        t.retain_reachable(&['清']);
+        let mut chars: Vec<char> = t.iter().map(|((k, _), _)| k).collect();
+        chars.sort();
+        chars.dedup();
+        assert_eq!(chars, vec!['月', '氵', '清', '青']);
+        assert!(t.primary_decomposition('可').is_none());
+        assert!(t.ids_has_subcomponent(&IDS::Char('清'), &IDS::Char('月')));
+    }
+
+    #[test]
+    fn display_span_of_child_path() {
+        let ids = parse("⿰氵⿱{龶}月").unwrap();
+        let text = ids.to_string();
+        assert_eq!(&text[ids.display_span(&[]).unwrap()], "⿰氵⿱{龶}月");
+        assert_eq!(&text[ids.display_span(&[1]).unwrap()], "⿱{龶}月");
+        assert_eq!(&text[ids.display_span(&[1, 0]).unwrap()], "{龶}");
+        assert_eq!(&text[ids.display_span(&[1, 1]).unwrap()], "月");
+        assert_eq!(ids.display_span(&[2]), None);
+        assert_eq!(ids.display_span(&[0, 0]), None);
+    }
+
+    #[test]
+    fn subcomponent_paths() {
+        let t = table("U+9752 青 ⿱龶月\nU+6E05 清 ⿰氵青\nU+5668 器 ⿳吅犬吅");
+        let ids = parse("⿰氵⿱龶月").unwrap();
+        assert_eq!(t.subcomponent_path(&ids, &IDS::Char('月')), Some(vec![1, 1]));
+        assert_eq!(t.subcomponent_path(&ids, &IDS::Char('口')), None);
+        // expansion stops at the leaf that had to be expanded
+        assert_eq!(t.subcomponent_path(&parse("⿰氵青").unwrap(), &IDS::Char('月')), Some(vec![1]));
+        let pattern = parse("⿱龶.").unwrap();
+        assert_eq!(t.matching_subcomponent_path(&ids, &pattern, '.'), Some(vec![1]));
+        assert_eq!(t.matching_subcomponent_path_at_depth(&ids, &pattern, '.', Some(1)), Some(vec![1]));
+        assert_eq!(t.matching_subcomponent_path_at_depth(&ids, &pattern, '.', Some(0)), None);
+        assert_eq!(t.matching_subcomponent_path_at_depth(&ids, &IDS::Char('龶'), '.', Some(2)), Some(vec![1, 0]));
+    }
+
+    #[test]
+    fn compose_checks_arity() {
+        let lr = IDC::new('⿰').unwrap();
+        assert_eq!(lr.compose(vec![IDS::Char('氵')]), Err(IDSError::ArityMismatch {
+            idc: lr,
+            expected: 2,
+            found: 1,
+        }));
+        assert_eq!(lr.compose(vec![IDS::Char('氵'), IDS::Char('青')]), Ok(parse("⿰氵青").unwrap()));
+        let tb3 = IDC::new('⿳').unwrap();
+        assert!(tb3.compose(vec![IDS::Char('亠'), IDS::Char('口')]).is_err());
+    }
+
+    #[test]
+    fn partial_pattern_matches_any_trailing_children() {
+        let t = table("U+6E05 清 ⿰氵青\nU+6CB3 河 ⿰氵可\nU+6D77 海 ⿰氵每\nU+65B0 新 ⿰亲斤\nU+6C41 汁 ⿱氵十");
+        let pattern = parse_partial("⿰氵").unwrap();
+        assert_eq!(pattern, IDS::Composition { idc: IDC::new('⿰').unwrap(), children: vec![IDS::Char('氵')] });
+        for k in ['清', '河', '海'] {
+            assert!(t.ids_match(&IDS::Char(k), &pattern, '.'));
+        }
+        assert!(!t.ids_match(&IDS::Char('新'), &pattern, '.'));
+        assert!(!t.ids_match(&IDS::Char('汁'), &pattern, '.'));
+        assert!(t.ids_match(&IDS::Char('新'), &parse_partial("⿰").unwrap(), '.'));
+        assert_eq!(parse_partial("⿰氵青").unwrap(), parse("⿰氵青").unwrap());
+        assert!(parse_partial("⿰氵青青").is_err());
+    }
+
+    #[test]
+    fn table_stats() {
+        let t = table("U+53E3 口 口\nU+5668 器 ⿳吅犬吅\nU+9752 青 ⿱龶⺝[G] ⿱龶月[T]\nU+6E05 清 ⿰氵⿱龶月");
+        assert_eq!(t.stats(), TableStats {
+            distinct_chars: 4,
+            total_entries: 5,
+            atomic_chars: 1,
+            max_depth: 2,
+            characters_with_multiple_variants: 1,
+        });
+        assert_eq!(IDS::Char('口').depth(), 0);
+    }
+
+    #[test]
+    fn ellipsis_matches_remaining_children() {
+        let t = table("U+6E05 清 ⿰氵青\nU+2A6A 𪩪 ⿲氵木木\nU+6C41 汁 ⿱氵十\nU+6797 林 ⿰木木");
+        let ellipsis = parse("⿰氵…").unwrap();
+        let wildcard = parse("⿰氵.").unwrap();
+        assert!(t.ids_match(&IDS::Char('清'), &ellipsis, '.'));
+        assert!(t.ids_match(&IDS::Char('清'), &wildcard, '.'));
+        // unlike the single-slot wildcard, the ellipsis swallows any number of children
+        assert!(t.ids_match(&IDS::Char('𪩪'), &ellipsis, '.'));
+        assert!(t.ids_match(&IDS::Char('清'), &parse_partial("⿲氵…").unwrap(), '.'));
+        assert!(!t.ids_match(&IDS::Char('清'), &parse_partial("⿲氵..").unwrap(), '.'));
+        assert!(!t.ids_match(&IDS::Char('汁'), &ellipsis, '.'));
+        assert!(!t.ids_match(&IDS::Char('林'), &ellipsis, '.'));
+        assert!(t.ids_match(&IDS::Char('𪩪'), &parse("⿲氵木…").unwrap(), '.'));
+        // zero remaining children are fine too
+        assert!(t.ids_match(&IDS::Char('清'), &parse("⿲氵青…").unwrap(), '.'));
+        assert!(!t.ids_match(&IDS::Char('清'), &parse("⿲氵木…").unwrap(), '.'));
+    }
+
+    #[test]
+    fn used_idcs_walks_nested_compositions() {
+        let t = table("U+53E3 口 口\nU+6E05 清 ⿰氵⿱龶月\nU+5668 器 ⿳吅犬吅");
+        let idcs: String = t.used_idcs().into_iter().map(IDC::as_char).collect();
+        assert_eq!(idcs, "⿰⿱⿳");
+    }
+
+    #[test]
+    fn grouping_bias_selects_ternary_regrouping() {
+        let mut t = IDSTable::default();
+        let ternary = parse("⿳亠口小").unwrap();
+        let leading = parse("⿱⿱亠口小").unwrap();
+        let trailing = parse("⿱亠⿱口小").unwrap();
+        assert!(t.ids_match(&ternary, &leading, '.'));
+        assert!(t.ids_match(&ternary, &trailing, '.'));
+        assert!(t.ids_match(&leading, &ternary, '.'));
+
+        t.set_grouping_bias(GroupingBias::Leading);
+        assert!(t.ids_match(&ternary, &leading, '.'));
+        assert!(!t.ids_match(&ternary, &trailing, '.'));
+
+        t.set_grouping_bias(GroupingBias::Trailing);
+        assert!(!t.ids_match(&ternary, &leading, '.'));
+        assert!(t.ids_match(&trailing, &ternary, '.'));
+    }
+
+    #[test]
+    fn ternary_compositions_match_across_groupings() {
+        let t = IDSTable::default();
+        let a = parse("⿳亠口⿱冖小").unwrap();
+        let b = parse("⿳⿱亠口冖小").unwrap();
+        assert!(t.ids_match(&a, &b, '.'));
+        assert!(t.ids_match(&a, &parse("⿳亠..").unwrap(), '.'));
+        assert!(!t.ids_match(&a, &parse("⿳⿱亠口冖口").unwrap(), '.'));
+        assert!(!t.ids_match(&a, &parse("⿲亠口⿱冖小").unwrap(), '.'));
+    }
+
+    #[test]
+    fn refine_narrows_previous_results() {
+        let t = table("U+6E05 清 ⿰氵青\nU+6674 晴 ⿰日青\nU+6CB3 河 ⿰氵可\nU+9752 青 ⿱龶月");
+        let mut previous: Vec<(char, Tag)> = t.iter()
+            .filter(|(_, ids)| t.ids_has_subcomponent(ids, &IDS::Char('青')))
+            .map(|((k, tag), _)| (k, tag.clone()))
+            .collect();
+        previous.sort();
+        let refined = t.refine(&previous, &IDS::Char('氵'));
+        assert_eq!(refined, vec![('清', Tag::Variant(vec![]))]);
+        assert!(t.refine(&refined, &IDS::Char('日')).is_empty());
+    }
+
+    #[test]
+    fn anon_tags_display_distinctly() {
+        let t = table("U+5341 十 ⿻一丨 ⿻一丨 ⿻一丨");
+        let mut rows: Vec<String> = t.iter().map(|((k, tag), _)| format!("{} {}", k, tag)).collect();
+        rows.sort();
+        assert_eq!(rows, vec!["十 ", "十 ~1", "十 ~2"]);
+    }
+
+    #[test]
+    fn load_auxiliary_numbers() {
+        let mut t = IDSTable::default();
+        t.load_strokes_from_string("U+53E3 口 3\n清 11\n\nbroken\n");
+        t.load_frequencies_from_string("的 100\n");
+        assert_eq!(t.stroke_count('口'), Some(3));
+        assert_eq!(t.stroke_count('清'), Some(11));
+        assert_eq!(t.stroke_count('河'), None);
+        assert_eq!(t.frequency('的'), Some(100));
+    }
+
+    #[test]
+    fn diff_ignores_anonymous_renumbering() {
+        let old = table("U+53E3 口 口\nU+6728 木 木\nU+6797 林 ⿰木木\nU+6797 林 ⿰木木\nU+6797 林 ⿱木木\nU+6E05 清 ⿰氵青[G]\n");
+        let new = table("U+53E3 口 口\nU+6797 林 ⿱木木\nU+6797 林 ⿰木木\nU+6797 林 ⿰木木\nU+6E05 清 ⿰氵靑[G]\nU+54C1 品 ⿱口⿰口口\n");
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, vec!['品']);
+        assert_eq!(diff.removed, vec!['木']);
+        assert_eq!(diff.changed, vec![DecompositionChange {
+            character: '清',
+            removed: vec![(Tag::from("G".to_string()), parse("⿰氵青").unwrap())],
+            added: vec![(Tag::from("G".to_string()), parse("⿰氵靑").unwrap())],
+        }]);
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn character_class_matches_any_member() {
+        let t = table("U+6C35 氵 氵\nU+51AB 冫 冫\nU+9752 青 青\nU+6E05 清 ⿰氵青\nU+51CA 凊 ⿰冫青\nU+60C5 情 ⿰忄青\n");
+        let pattern = parse_partial("⿰[氵冫]青").unwrap();
+        assert_eq!(pattern, IDS::Composition {
+            idc: IDC::new('⿰').unwrap(),
+            children: vec![IDS::OneOf(vec!['氵', '冫']), IDS::Char('青')],
+        });
+        assert_eq!(pattern.to_string(), "⿰[氵冫]青");
+        assert!(t.ids_match(&IDS::Char('清'), &pattern, '.'));
+        assert!(t.ids_match(&IDS::Char('凊'), &pattern, '.'));
+        assert!(!t.ids_match(&IDS::Char('情'), &pattern, '.'));
+
+        // after a complete IDS, brackets are still a tag
+        let tagged = parse_tagged("⿰氵青[GT]").unwrap();
+        assert_eq!(tagged.ids, parse("⿰氵青").unwrap());
+        assert!(tagged.tag.has_region("T"));
+    }
+
+    #[test]
+    fn pmatch_regroups_nested_ternaries() {
+        let t = table("U+4E00 一 一\nU+53E3 口 口\nU+6728 木 木\nU+4E09 三 ⿳一一一\nU+6A3E 樾 ⿰木⿳口一木\nU+6797 林 ⿰木木\n");
+        let pattern = parse_partial("⿱口一").unwrap();
+        assert!(!t.ids_match(&IDS::Char('樾'), &pattern, '.'));
+        assert!(t.ids_has_matching_subcomponent(&IDS::Char('樾'), &pattern, '.'));
+        assert!(t.ids_has_matching_subcomponent(&IDS::Char('樾'), &parse_partial("⿱一木").unwrap(), '.'));
+        assert!(!t.ids_has_matching_subcomponent(&IDS::Char('樾'), &parse_partial("⿱口木").unwrap(), '.'));
+        assert!(!t.ids_has_matching_subcomponent(&IDS::Char('林'), &parse_partial("⿱木木").unwrap(), '.'));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn load_from_reader_reads_lines() {
+        let data = "U+6728 木 木\r\nU+6797 林 ⿰木木\r\n".as_bytes().to_vec();
+        let t = IDSTable::load_from_reader(io::Cursor::new(data)).unwrap();
+        assert_eq!(t.primary_decomposition('林'), Some(&parse("⿰木木").unwrap()));
+        assert_eq!(t.iter().count(), 2);
+
+        let invalid = io::Cursor::new(vec![0xff, b'\n']);
+        assert!(IDSTable::load_from_reader(invalid).is_err());
+    }
+
+    #[test]
+    fn enclosed_in_matches_by_frame() {
+        let t = table("U+53E3 口 口\nU+56D7 囗 囗\nU+9580 門 門\nU+5E7F 广 广\nU+56DE 回 ⿴囗口\nU+554F 問 ⿵門口\nU+5E97 店 ⿸广占\nU+5EAB 庫 ⿸广車\nU+5442 呂 ⿱口口\n");
+        let matching = |pattern: &IDS| -> Vec<char> {
+            let mut found: Vec<char> = t.iter().filter(|(_, ids)| t.ids_match(ids, pattern, '.')).map(|((k, _), _)| k).collect();
+            found.sort();
+            found
+        };
+        assert_eq!(matching(&IDS::enclosed_in('門', IDS::Char('口'))), vec!['問']);
+        assert_eq!(matching(&IDS::enclosed_in('囗', IDS::Char('口'))), vec!['回']);
+        assert_eq!(matching(&IDS::enclosed_in('广', IDS::Char('.'))), vec!['店', '庫']);
+        assert_eq!(matching(&IDS::enclosed_in('.', IDS::Char('口'))), vec!['回']);
+        // nor does a wildcard frame let other surround IDCs stand in
+        assert_eq!(matching(&parse("⿺..").unwrap()), Vec::<char>::new());
+        assert_eq!(matching(&parse("⿸..").unwrap()), vec!['店', '庫']);
+        // written with its own IDC, a surround pattern still works as before
+        assert_eq!(matching(&parse("⿸广車").unwrap()), vec!['庫']);
+        assert!(!t.ids_match(&IDS::Char('呂'), &IDS::enclosed_in('口', IDS::Char('口')), '.'));
+    }
+
+    #[test]
+    fn direct_component_is_top_level_only() {
+        let t = table("U+53E3 口 口\nU+5403 吃 ⿰口乞\nU+53EB 叫 ⿰口丩\nU+543E 吾 ⿱五口\nU+8A9E 語 ⿰言吾\nU+8304 茄 ⿱艹⿰力口\n");
+        let needle = IDS::Char('口');
+        assert!(t.has_direct_component('吃', &needle));
+        assert!(t.has_direct_component('叫', &needle));
+        assert!(!t.has_direct_component('語', &needle));
+        assert!(!t.has_direct_component('茄', &needle));
+        assert!(!t.has_direct_component('口', &needle));
+        assert!(t.has_direct_component('茄', &parse("⿰力口").unwrap()));
+    }
+
+    #[test]
+    fn map_chars_keeps_structure() {
+        let ids = parse("⿰氵⿱{龷}氵").unwrap();
+        let folded = ids.map_chars(|c| if c == '氵' { '水' } else { c });
+        assert_eq!(folded, parse("⿰水⿱{龷}水").unwrap());
+        assert_eq!(IDS::OneOf(vec!['氵', '冫']).map_chars(|_| '水'), IDS::OneOf(vec!['水', '水']));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_file_round_trips() {
+        let original = table("U+53E3 口 口\nU+6797 林 ⿰木木 ⿰木木 ⿱木木[GT]\nU+6E05 清 ⿰氵青[G] ⿰氵靑[UCS2003]\nU+4E0D 不 ⿱一卜[KP,]\nU+2B81D 𫠝 ⿰{柬中}攵 ⿰&CDP-8BF1;攵[J]\n");
+        let path = std::env::temp_dir().join(format!("hanzi-search-round-trip-{}.txt", std::process::id()));
+        original.write_file(&path).unwrap();
+        let reloaded = IDSTable::load_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(original.diff(&reloaded).is_empty(), "{:?}", original.diff(&reloaded));
+        assert_eq!(original.iter().count(), reloaded.iter().count());
+
+        let mut out = vec![];
+        table("U+6797 林 ⿰木木[G]\n").write_to(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "U+6797\t林\t⿰木木[G]\n");
+    }
+
+    #[test]
+    fn codepoint_column_is_checked() {
+        let mut issues = vec![];
+        let mut t = IDSTable::default();
+        t.ingest_line(1, "U+6797 林 ⿰木木", &mut issues);
+        t.ingest_line(2, "U+20000 𠀀 𠀀", &mut issues);
+        t.ingest_line(3, "U+6798 林 ⿱木木[G]", &mut issues);
+        t.ingest_line(4, "6E05 清 ⿰氵青", &mut issues);
+        assert_eq!(issues, vec![
+            ValidationIssue::CodepointMismatch { line: 3, character: '林', codepoint: "U+6798".to_string() },
+            ValidationIssue::CodepointMismatch { line: 4, character: '清', codepoint: "6E05".to_string() },
+        ]);
+        assert_eq!(issues[0].to_string(), "line 3: code point U+6798 does not match 林 (U+6797)");
+        // the line is still loaded under the character
+        assert_eq!(t.tags[&'林'].len(), 2);
+    }
+
+    #[test]
+    fn variation_selector_matches_base() {
+        let t = table("U+66F7 曷 ⿱日匃\nU+845B 葛 ⿱艹曷\nU+9AFA 髺 ⿱髟葛\u{E0100}\nU+8B01 謁 ⿰言曷\n");
+        let ivs = parse("葛\u{E0100}").unwrap();
+        assert_eq!(ivs, IDS::CharWithVS { base: '葛', vs: '\u{E0100}' });
+        assert_eq!(ivs.to_string(), "葛\u{E0100}");
+        assert_eq!(parse("⿱髟葛\u{E0100}").unwrap().leaves(), vec!['髟', '葛']);
+
+        let haystack = t.primary_decomposition('髺').unwrap();
+        assert!(t.ids_match(haystack, &parse("⿱髟葛").unwrap(), '.'));
+        assert!(t.ids_match(haystack, &parse("⿱髟⿱艹曷").unwrap(), '.'));
+        assert!(t.ids_has_subcomponent(haystack, &IDS::Char('曷')));
+        assert!(t.ids_has_matching_subcomponent(haystack, &parse_partial("⿱艹曷").unwrap(), '.'));
+
+        let mut t = t;
+        t.set_exact_variation_selectors(true);
+        let haystack = t.primary_decomposition('髺').unwrap();
+        assert!(!t.ids_match(haystack, &parse("⿱髟葛").unwrap(), '.'));
+        assert!(t.ids_match(haystack, &parse("⿱髟葛\u{E0100}").unwrap(), '.'));
+        assert!(!t.ids_match(haystack, &parse("⿱髟葛\u{E0101}").unwrap(), '.'));
+        assert!(t.ids_has_subcomponent(haystack, &ivs));
+        assert!(!t.ids_has_subcomponent(haystack, &IDS::Char('曷')));
+    }
+
+    #[test]
+    fn merge_variants_by_region_folds_identical() {
+        let mut t = table("U+6E05 清 ⿰氵青[G] ⿰氵青[T] ⿰氵靑[K] ⿰氵青[J]\nU+6797 林 ⿰木木 ⿰木木\n");
+        assert_eq!(t.merge_variants_by_region(), 2);
+        assert_eq!(t.variants('清'), vec![
+            (Tag::from("GTJ".to_string()), parse("⿰氵青").unwrap()),
+            (Tag::from("K".to_string()), parse("⿰氵靑").unwrap()),
+        ]);
+        assert_eq!(t.variants('林').len(), 2);
+        assert_eq!(t.merge_variants_by_region(), 0);
+        assert_eq!(t.find_leaf_only('青'), vec![('清', Tag::from("GTJ".to_string()))]);
+
+        // merging 林[G] and 林[T] would give the tag of another decomposition
+        let mut t = table("U+6797 林 ⿰木木[G] ⿰木木[T] ⿱木木[GT]\n");
+        assert_eq!(t.merge_variants_by_region(), 0);
+        assert_eq!(t.variants('林').len(), 3);
+        assert_eq!(t.decomposition('林', &Tag::from("GT".to_string())), Some(&parse("⿱木木").unwrap()));
+        let mut t = table("U+6797 林 ⿰木木[GT] ⿰木木[T]\n");
+        assert_eq!(t.merge_variants_by_region(), 1);
+        assert_eq!(t.variants('林'), vec![(Tag::from("GT".to_string()), parse("⿰木木").unwrap())]);
+    }
+
+    #[test]
+    fn leaf_count_counts_written_leaves() {
+        assert_eq!(parse("口").unwrap().leaf_count(), 1);
+        assert_eq!(parse("⿰口十").unwrap().leaf_count(), 2);
+        assert_eq!(parse("⿳⿰口口犬⿰口口").unwrap().leaf_count(), 5);
+        assert_eq!(parse("⿰{柬中}攵").unwrap().leaf_count(), 2);
+    }
+
+    #[test]
+    fn fold_width_matches_fullwidth_specials() {
+        let mut t = table("U+E000 \u{E000} ⿰{ＡＢＣ}口[Ｇ]\n");
+        let pattern = parse("⿰{ABC}口").unwrap();
+        let ids = t.primary_decomposition('\u{E000}').unwrap().clone();
+        assert!(!t.ids_match(&ids, &pattern, '.'));
+        t.set_fold_width(true);
+        let ids = t.primary_decomposition('\u{E000}').unwrap().clone();
+        assert_eq!(ids, pattern);
+        assert!(t.ids_match(&ids, &pattern, '.'));
+        assert!(t.ids_match(&parse("{ＡＢＣ}").unwrap(), &parse("{ABC}").unwrap(), '.'));
+        assert!(t.variants('\u{E000}').iter().any(|(tag, _)| tag.has_region("G")));
+    }
+
+    #[test]
+    fn child_subcomponent_matches_inside_children() {
+        let mut t = table("U+6C35 氵 氵\nU+9752 青 青\nU+6708 月 月\nU+E000 \u{E000} ⿰氵⿱青月\n");
+        let ids = t.primary_decomposition('\u{E000}').unwrap().clone();
+        assert!(!t.ids_match(&ids, &parse("⿰氵青").unwrap(), '.'));
+        t.set_child_subcomponent(true);
+        assert!(t.ids_match(&ids, &parse("⿰氵青").unwrap(), '.'));
+        assert!(t.ids_match(&ids, &parse("⿰氵月").unwrap(), '.'));
+        assert!(!t.ids_match(&ids, &parse("⿰青氵").unwrap(), '.'));
+        assert!(!t.ids_match(&ids, &parse("⿱氵青").unwrap(), '.'));
+    }
+
+    #[test]
+    fn child_subcomponent_keeps_region_and_sides() {
+        let mut t = table("U+6C35 氵 氵\nU+9752 青 ⿱龶⺝[G] ⿱龶月[J]\nU+E000 \u{E000} ⿰氵⿱青口\nU+E001 \u{E001} ⿱⿱十口⿰氵⿱青月\n");
+        t.set_child_subcomponent(true);
+        let ids = t.primary_decomposition('\u{E000}').unwrap().clone();
+        let pattern = parse("⿰氵⿱龶月").unwrap();
+        assert!(t.ids_match(&ids, &pattern, '.'));
+        let mut metrics = SearchMetrics::default();
+        assert!(t.ids_match_in_region_metered(&ids, &pattern, '.', "J", &mut metrics));
+        assert!(metrics.expansions > 0);
+        assert!(!t.ids_match_in_region(&ids, &pattern, '.', "G"));
+        // the regrouped side is the pattern: its parts are still looked for
+        // inside the stored ones, not the other way round
+        let ids = t.primary_decomposition('\u{E001}').unwrap().clone();
+        assert!(t.ids_match(&ids, &parse("⿳十口⿰氵青").unwrap(), '.'));
+        let stored = parse("⿳十口⿰氵⿱青月").unwrap();
+        assert!(!t.ids_match(&parse("⿱⿱十口⿰氵青").unwrap(), &stored, '.'));
+    }
+
+    #[test]
+    fn annotate_pairs_characters_with_decompositions() {
+        let t = table("U+53E3 口 口\nU+5341 十 十\nU+53F6 叶 ⿰口十\n");
+        let annotated = t.annotate("叶 口，\u{20DD}十\u{FE00}");
+        let chars: Vec<char> = annotated.iter().map(|(c, _)| *c).collect();
+        assert_eq!(chars, vec!['叶', '口', '，', '十']);
+        assert_eq!(annotated[0].1, Some(&parse("⿰口十").unwrap()));
+        assert_eq!(annotated[1].1, Some(&IDS::Char('口')));
+        assert_eq!(annotated[2].1, None);
+    }
+
+    #[test]
+    fn by_radical_subtracts_the_form_strokes() {
+        let mut t = table("U+6728 木 木\nU+5B50 子 子\nU+53E3 口 口\nU+6C35 氵 氵\nU+9752 青 青\n\
+            U+6797 林 ⿰木木\nU+674E 李 ⿱木子\nU+674F 杏 ⿱木口\nU+6E05 清 ⿰氵青\nU+6C34 水 水\nU+6C38 永 永\n");
+        t.load_strokes_from_string("木 4\n子 3\n口 3\n氵 3\n青 8\n林 8\n李 7\n杏 7\n清 11\n水 4\n");
+        assert_eq!(t.by_radical('木', 3), vec!['李', '杏']);
+        assert_eq!(t.by_radical('木', 4), vec!['林']);
+        assert_eq!(t.by_radical('木', 0), vec!['木']);
+        assert!(t.by_radical('水', 8).is_empty());
+        t.load_radicals_from_string("水氵氺\n");
+        assert_eq!(t.by_radical('水', 8), vec!['清']);
+        assert_eq!(t.by_radical('水', 0), vec!['水', '氵']);
+    }
+
+    #[test]
+    fn ids_order_is_canonical() {
+        let mut all: Vec<IDS> = ["⿱口口", "⿰口十", "{柬中}", "口", "⿰口口", "十"].iter().map(|s| parse(s).unwrap()).collect();
+        all.sort();
+        let sorted: Vec<String> = all.iter().map(IDS::to_string).collect();
+        assert_eq!(sorted, vec!["十", "口", "{柬中}", "⿰口十", "⿰口口", "⿱口口"].into_iter().map(String::from).collect::<Vec<_>>());
+        let set: alloc::collections::BTreeSet<IDS> = all.into_iter().collect();
+        assert_eq!(set.len(), 6);
+    }
+
+    #[test]
+    fn pmatch_expands_leaves_along_the_pattern_direction() {
+        let t = table("U+4E00 一 一\nU+53E3 口 口\nU+6728 木 木\nU+5415 吕 ⿱口口\nU+53E4 古 ⿱十口\nU+5341 十 十\nU+E000 \u{E000} ⿱一口\nU+E001 \u{E001} ⿱\u{E000}口\nU+E002 \u{E002} ⿰木\u{E001}\n");
+        let pattern = parse_partial("⿱一吕").unwrap();
+        // stored as U+E000 over 口 with U+E000 as a leaf: the pattern groups the parts differently
+        assert!(t.ids_has_matching_subcomponent(&parse("⿱\u{E000}口").unwrap(), &pattern, '.'));
+        assert!(t.ids_has_matching_subcomponent(t.primary_decomposition('\u{E002}').unwrap(), &pattern, '.'));
+        assert!(t.ids_has_matching_subcomponent(t.primary_decomposition('\u{E002}').unwrap(), &parse_partial("⿱一⿱口口").unwrap(), '.'));
+        assert!(!t.ids_has_matching_subcomponent(t.primary_decomposition('\u{E002}').unwrap(), &parse_partial("⿱十吕").unwrap(), '.'));
+    }
+
+    #[test]
+    fn orphan_components_by_reference_count() {
+        let t = table("U+53E3 口 口\nU+53F6 叶 ⿰口十\nU+5341 十 ⿻一丨\nU+E000 \u{E000} ⿱十丨\n");
+        assert_eq!(t.orphan_components(), vec!['丨', '一']);
+        assert!(table("U+53E3 口 口\nU+5415 吕 ⿱口口\n").orphan_components().is_empty());
+    }
+
+    #[test]
+    fn precomputed_closures_answer_like_expanding() {
+        let data = "U+53E3 口 口\nU+5341 十 十\nU+53E4 古 ⿱十口\nU+80E1 胡 ⿰古月\nU+6E56 湖 ⿰氵胡\nU+E000 \u{E000} ⿱\u{E000}口\n";
+        let mut t = table(data);
+        let queries = [('湖', '十'), ('湖', '古'), ('湖', '木'), ('古', '月'), ('口', '十')];
+        let expected: Vec<bool> = queries.iter().map(|&(h, n)| t.ids_has_subcomponent(&IDS::Char(h), &IDS::Char(n))).collect();
+        t.precompute_closures(8);
+        assert!(t.closures.contains_key(&'湖') && t.closures.contains_key(&'\u{E000}'));
+        let cached: Vec<bool> = queries.iter().map(|&(h, n)| t.ids_has_subcomponent(&IDS::Char(h), &IDS::Char(n))).collect();
+        assert_eq!(cached, expected);
+        // cyclic entries no longer recurse forever
+        assert!(!t.ids_has_subcomponent(&IDS::Char('\u{E000}'), &IDS::Char('十')));
+        t.precompute_closures(1);
+        assert!(!t.closures.contains_key(&'湖') && t.closures.contains_key(&'古'));
+        t.insert_entry('月', Tag::Variant(vec![]), IDS::Char('月'));
+        assert!(t.closures.is_empty());
+    }
+
+    #[test]
+    fn settings_drop_precomputed_closures() {
+        let mut t = table("U+6E05 清 ⿰氵〾青\nU+9752 青 ⿱龶月\nU+6708 月 月\nU+6B65 步 ⿱止↔少\nU+6B62 止 止");
+        t.set_lenient_variation_indicators(true);
+        t.precompute_closures(8);
+        assert!(t.ids_has_subcomponent(&IDS::Char('清'), &IDS::Char('月')));
+        t.set_lenient_variation_indicators(false);
+        assert!(t.closures.is_empty());
+        assert!(!t.ids_has_subcomponent(&IDS::Char('清'), &IDS::Char('月')));
+
+        t.precompute_closures(8);
+        t.exact_modifiers = true;
+        assert!(!t.ids_has_subcomponent(&IDS::Char('步'), &IDS::Char('止')));
+        t.set_exact_modifiers(false);
+        t.precompute_closures(8);
+        assert!(t.ids_has_subcomponent(&IDS::Char('步'), &IDS::Char('止')));
+        t.set_preferred_region(Some(String::from("G")));
+        assert!(t.closures.is_empty());
+    }
+
+    #[test]
+    fn common_substructure_prefers_shared_characters() {
+        let t = table("U+9F36 龶 龶\nU+6708 月 月\nU+9752 青 ⿱龶月\nU+6C35 氵 氵\nU+8A00 言 言\n\
+            U+6E05 清 ⿰氵青\nU+8ACB 請 ⿰言青\nU+6709 有 ⿸𠂇月\nU+53E3 口 口\n");
+        assert_eq!(t.common_substructure('清', '請'), Some(IDS::Char('青')));
+        assert_eq!(t.common_substructure('清', '青'), Some(IDS::Char('青')));
+        assert_eq!(t.common_substructure('清', '有'), Some(IDS::Char('月')));
+        assert_eq!(t.common_substructure('清', '口'), None);
+    }
+
+    #[test]
+    fn modifiers_wrap_components() {
+        let ids = parse("⿰止↔止").unwrap();
+        assert_eq!(ids, IDS::Composition {
+            idc: IDC('⿰'),
+            children: vec![IDS::Modified { base: Box::new(IDS::Char('止')), modifier: '↔' }, IDS::Char('止')],
+        });
+        assert_eq!(ids.to_string(), "⿰止↔止");
+        assert_eq!(ids.leaves(), vec!['止', '止']);
+        assert_eq!(parse_partial("⿰{反}↷").unwrap().to_string(), "⿰{反}↷");
+
+        let mut t = table("U+6B62 止 止\nU+E000 \u{E000} ⿰止↔止\n");
+        let stored = t.primary_decomposition('\u{E000}').unwrap().clone();
+        assert!(t.ids_match(&stored, &parse("⿰止止").unwrap(), '.'));
+        assert!(t.ids_has_subcomponent(&stored, &IDS::Char('止')));
+        t.set_exact_modifiers(true);
+        assert!(!t.ids_match(&stored, &parse("⿰止止").unwrap(), '.'));
+        assert!(t.ids_match(&stored, &parse("⿰止↔.").unwrap(), '.'));
+        assert!(!t.ids_match(&stored, &parse("⿰止↷.").unwrap(), '.'));
+        assert!(t.ids_has_subcomponent(&stored, &parse("止↔").unwrap()));
+        assert!(t.ids_has_subcomponent(&stored, &IDS::Char('止')));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn shrink_to_fit_releases_capacity() {
+        let mut t = table(include_str!("../chai.txt"));
+        let before = (t.table.capacity(), t.approximate_memory_bytes());
+        t.retain_reachable(&['清']);
+        t.shrink_to_fit();
+        assert!(t.table.capacity() < before.0);
+        assert!(t.approximate_memory_bytes() < before.1 / 100);
+        assert!(t.approximate_memory_bytes() > 0);
+        assert!(t.primary_decomposition('青').is_some());
+    }
+
+    #[test]
+    fn ids_match_in_region_only_expands_that_region() {
+        let t = table("U+9F36 龶 龶\nU+6708 月 月\nU+2E9D ⺝ ⺝\nU+6C35 氵 氵\n\
+            U+9752 青 ⿱龶⺝[G] ⿱龶月[J]\nU+6E05 清 ⿰氵青\nU+6CB3 河 ⿰氵可[J]\n");
+        let stored = t.primary_decomposition('清').unwrap();
+        let g = parse("⿰氵⿱龶⺝").unwrap();
+        let j = parse("⿰氵⿱龶月").unwrap();
+        assert!(t.ids_match(stored, &g, '.') && t.ids_match(stored, &j, '.'));
+        assert!(!t.ids_match_in_region(stored, &g, '.', "J"));
+        assert!(t.ids_match_in_region(stored, &j, '.', "J"));
+        assert!(t.ids_match_in_region(stored, &g, '.', "G"));
+        // without variants of the region, all of them are tried
+        assert!(t.ids_match_in_region(stored, &g, '.', "K"));
+    }
+
+    #[test]
+    fn subcomponent_count_counts_through_expansions() {
+        let t = table("U+53E3 口 口\nU+5405 吅 ⿰口口\nU+54C1 品 ⿱口⿰口口\nU+35CA 㗊 ⿱吅吅\nU+72AC 犬 犬\nU+5668 器 ⿳吅犬吅\n");
+        let count = |k: char| t.subcomponent_count(&IDS::Char(k), &IDS::Char('口'));
+        assert_eq!((count('口'), count('吅'), count('品'), count('㗊'), count('器'), count('犬')), (1, 2, 3, 4, 4, 0));
+        assert_eq!(t.subcomponent_count(&IDS::Char('㗊'), &IDS::Char('吅')), 2);
+    }
+
+    #[test]
+    fn replace_leaves_and_subtrees() {
+        let ids = parse("⿱口⿰口口").unwrap();
+        assert_eq!(ids.replace(&IDS::Char('口'), &IDS::Char('日')).to_string(), "⿱日⿰日日");
+        assert_eq!(ids.replace(&parse("⿰口口").unwrap(), &IDS::Char('吅')).to_string(), "⿱口吅");
+        assert_eq!(ids.replace(&IDS::Char('木'), &IDS::Char('日')), ids);
+
+        let mut t = table("U+53E3 口 口\nU+5405 吅 ⿰口口\nU+54C1 品 ⿱口⿰口口\nU+5668 器 ⿳⿰口口犬⿰口口\n");
+        assert_eq!(t.replace_everywhere(&parse("⿰口口").unwrap(), &IDS::Char('吅')), 4);
+        assert_eq!(t.primary_decomposition('器').unwrap().to_string(), "⿳吅犬吅");
+        // 吅 decomposed to exactly the target, so it now refers to itself
+        assert_eq!(t.primary_decomposition('吅'), Some(&IDS::Char('吅')));
+        assert_eq!(t.find_leaf_only('犬'), vec![('器', Tag::Variant(vec![]))]);
+        assert_eq!(t.replace_everywhere(&IDS::Char('木'), &IDS::Char('日')), 0);
+    }
+
+    #[test]
+    fn parse_reports_missing_components() {
+        let missing = |idc: char, position, expected, found| ParseError::MissingComponents { idc: IDC::new(idc).unwrap(), position, expected, found };
+        assert_eq!(parse("⿱⿰日"), Err(missing('⿰', 1, 2, 1)));
+        assert_eq!(parse("⿱日"), Err(missing('⿱', 0, 2, 1)));
+        assert_eq!(parse("⿰日⿳口口"), Err(missing('⿳', 2, 3, 2)));
+        assert_eq!(parse("⿱⿰日").unwrap_err().to_string(), "⿰ at position 1 takes 2 components, but the input ends after 1");
+        assert_eq!(parse("⿰日月木"), Err(ParseError::TrailingInput { position: 3 }));
+        assert_eq!(parse("⿰日{"), Err(ParseError::Invalid { position: 2 }));
+        assert_eq!(parse("⿱口⿰日{"), Err(ParseError::Invalid { position: 4 }));
+        assert!(parse_tagged("⿱⿰日[G]").is_err());
+    }
+
+    #[test]
+    fn positional_parts() {
+        let t = table("U+6E05 清 ⿰氵青\nU+82B1 花 ⿱艹化\nU+8857 街 ⿲彳口亍\nU+56DE 回 ⿴口口\nU+6C35 氵 氵");
+        assert_eq!(t.left_of('清'), Some(IDS::Char('氵')));
+        assert_eq!(t.right_of('清'), Some(IDS::Char('青')));
+        assert_eq!(t.top_of('花'), Some(IDS::Char('艹')));
+        assert_eq!(t.bottom_of('花'), Some(IDS::Char('化')));
+        assert_eq!(t.right_of('街'), Some(IDS::Char('亍')));
+        assert_eq!(t.top_of('清'), None);
+        assert_eq!(t.left_of('回'), None);
+        assert_eq!(t.left_of('氵'), None);
+        assert_eq!(t.left_of('河'), None);
+    }
+
+    #[test]
+    fn neighbors_within_radius() {
+        let t = table("U+65E5 日 日\nU+6708 月 月\nU+660E 明 ⿰日月\nU+840C 萌 ⿱艹明\nU+6674 晴 ⿰日青\nU+9752 青 ⿱龶月\nU+6728 木 木");
+        let near: Set<char> = t.neighbors('明', 1);
+        assert_eq!(near, ['日', '月', '萌'].into_iter().collect());
+        let far = t.neighbors('明', 2);
+        assert_eq!(far, ['日', '月', '萌', '艹', '晴', '青'].into_iter().collect());
+        assert!(t.neighbors('明', 0).is_empty());
+        assert!(t.neighbors('木', 3).is_empty());
+    }
+
+    #[test]
+    fn structural_prefixes() {
+        let t = table("U+53E3 口 口\nU+5442 吕 ⿱口口\nU+20B9B 𠮛 ⿳口口一\nU+4E00 一 一\nU+5F53 当 ⿱⿱口口一\n\
+                       U+53F1 叱 ⿰口匕\nU+56DE 回 ⿴口口\nU+56DA 囚 ⿴口人\nU+54C1 品 ⿱口⿰口口");
+        assert!(t.is_structural_prefix('吕', '𠮛'));
+        assert!(t.is_structural_prefix('吕', '当'));
+        assert!(t.is_structural_prefix('吕', '吕'));
+        assert!(!t.is_structural_prefix('𠮛', '吕'));
+        assert!(!t.is_structural_prefix('吕', '品'));
+        assert!(!t.is_structural_prefix('叱', '吕'));
+        assert!(t.is_structural_prefix('回', '回'));
+        assert!(!t.is_structural_prefix('回', '囚'));
+        assert!(!t.is_structural_prefix('口', '吕'));
+        assert!(!t.is_structural_prefix('吕', '河'));
+    }
+
+    #[test]
+    fn parse_trims_whitespace() {
+        let expected = parse("⿰氵青").unwrap();
+        assert_eq!(parse(" ⿰氵青 "), Ok(expected.clone()));
+        assert_eq!(parse("\u{3000}⿰氵青\u{3000}\t"), Ok(expected.clone()));
+        assert_eq!(parse_partial("\u{3000}⿰氵"), parse_partial("⿰氵"));
+        assert_eq!(parse_tagged(" ⿰氵青[G]\u{3000}").unwrap().ids, expected);
+        assert!(parse("⿰氵 青").is_err());
+        assert_eq!(parse("\u{3000}⿰氵"), Err(ParseError::MissingComponents { idc: IDC('⿰'), position: 1, expected: 2, found: 1 }));
+
+        let strict = ParseOptions { trim: false };
+        assert_eq!(parse_with(" ⿰氵青", &strict), Err(ParseError::TrailingInput { position: 1 }));
+        assert_eq!(parse_with("⿰氵青\u{3000}", &strict), Err(ParseError::TrailingInput { position: 3 }));
+        assert_eq!(parse_with("⿰氵青", &strict), Ok(expected));
+    }
+
+    #[test]
+    fn validate_ids_before_insertion() {
+        let t = table("U+6C35 氵 氵\nU+9752 青 青\nU+53E3 口 口\nU+56DE 回 ⿴口口[G] 回[T]");
+        let untagged = Tag::Variant(vec![]);
+        assert!(t.validate_ids('清', &untagged, &parse("⿰氵青").unwrap()).is_empty());
+        assert_eq!(t.validate_ids('清', &untagged, &parse("⿰氵⿱龶月").unwrap()), vec![
+            ValidationIssue::UnknownComponent { character: '清', tag: untagged.clone(), component: '龶' },
+            ValidationIssue::UnknownComponent { character: '清', tag: untagged.clone(), component: '月' },
+        ]);
+        assert_eq!(t.validate_ids('清', &untagged, &parse("⿰氵清").unwrap()), vec![
+            ValidationIssue::SelfDecomposition { character: '清', tag: untagged.clone() },
+            ValidationIssue::UnknownComponent { character: '清', tag: untagged.clone(), component: '清' },
+        ]);
+        assert!(t.validate_ids('河', &untagged, &IDS::Char('河')).is_empty());
+        assert_eq!(t.validate_ids('回', &untagged, &IDS::Char('回')),
+                   vec![ValidationIssue::SelfDecomposition { character: '回', tag: untagged }]);
+    }
+
+    #[test]
+    fn idc_directions() {
+        let direction = |c| IDC::new(c).unwrap().direction();
+        assert_eq!(direction('⿲'), Direction::Hort);
+        assert_eq!(direction('⿳'), Direction::Vert);
+        assert!("⿴⿵⿶⿷⿸⿹⿺⿼⿽".chars().all(|c| direction(c) == Direction::Surround && IDC(c).is_surround()));
+        assert_eq!(direction('⿻'), Direction::Overlaid);
+        assert!("⿾⿿㇯".chars().all(|c| direction(c) == Direction::Other));
+        assert!(IDC('⿴').is_same_direction(IDC('⿸')));
+        assert!(!IDC('⿴').is_same_direction(IDC('⿻')));
+        assert!(!IDC('⿻').is_same_direction(IDC('⿼')));
+        assert!(Direction::Hort.is_linear() && !Direction::Surround.is_linear());
+
+        // sharing a direction only lets linear compositions stand in for each other
+        let t = table("U+53E3 口 口\nU+5182 冂 冂\nU+540C 同 ⿵冂⿱一口");
+        assert!(t.ids_match(&IDS::Char('同'), &parse("⿵冂…").unwrap(), NO_WILDCARD));
+        assert!(!t.ids_match(&IDS::Char('同'), &parse("⿴冂…").unwrap(), NO_WILDCARD));
+        assert!(t.ids_match(&IDS::Char('同'), &parse("⿴冂⿱一口").unwrap(), NO_WILDCARD));
+    }
+
+    #[test]
+    fn entries_with_specials() {
+        let t = table("U+E000 \u{E000} ⿰{甲}口\nU+E001 \u{E001} ⿱{甲}{甲}[G] ⿱{乙}一[T]\nU+E002 \u{E002} ⿰\u{E001}口\nU+E003 \u{E003} ⿰{乙}口");
+        let g = Tag::from("G".to_string());
+        let t_tag = Tag::from("T".to_string());
+        assert_eq!(t.entries_with_special("甲"), vec![('\u{E000}', Tag::Variant(vec![])), ('\u{E001}', g)]);
+        assert_eq!(t.entries_with_special("乙"), vec![('\u{E001}', t_tag), ('\u{E003}', Tag::Variant(vec![]))]);
+        assert!(t.entries_with_special("丙").is_empty());
+        assert_eq!(t.specials(), vec![("甲".to_string(), 3), ("乙".to_string(), 2)]);
+    }
+
+    #[test]
+    fn load_with_alternate_layout() {
+        let options = LoadOptions { char_column: 2, ids_start_column: 1, codepoint_column: None, delimiter: Some('\t'), ..LoadOptions::default() };
+        let data = "# comment\t\t\n1\t⿰氵青\t清\n2\t⿱龶月[T]\t青\t⿱龶⺝[G]\n3\t\t月\n\n";
+        let t = IDSTable::load_from_string_with_options(data, &options).unwrap();
+        assert_eq!(t.primary_decomposition('清'), Some(&parse("⿰氵青").unwrap()));
+        assert_eq!(t.variants('青').len(), 2);
+        assert_eq!(t.decomposition('青', &Tag::from("G".to_string())), Some(&parse("⿱龶⺝").unwrap()));
+        assert!(!t.contains_char('月'));
+        assert!(!t.contains_char('\t'));
+
+        let mut issues = vec![];
+        let mut t = IDSTable::default();
+        t.ingest_line_with(1, "清\t⿰氵青", &options, &mut issues);
+        assert_eq!(issues, vec![ValidationIssue::Malformed { line: 1 }]);
+        assert_eq!(IDSTable::load_from_string_with_options("U+6E05 清 ⿰氵青", &LoadOptions::default()).unwrap().iter().count(), 1);
+    }
+
+    #[test]
+    fn iter_nodes_in_pre_order() {
+        let ids = parse("⿰氵⿱{甲}止↔").unwrap();
+        let nodes: Vec<String> = ids.iter_nodes().map(|n| n.to_string()).collect();
+        assert_eq!(nodes, vec!["⿰氵⿱{甲}止↔", "氵", "⿱{甲}止↔", "{甲}", "止↔", "止"]);
+        assert_eq!(ids.iter_nodes().filter(|n| matches!(n, IDS::Special(_))).count(), 1);
+        assert_eq!(IDS::Char('口').iter_nodes().collect::<Vec<_>>(), vec![&IDS::Char('口')]);
+    }
+
+    #[test]
+    fn minimal_pairs_differ_in_one_child() {
+        let t = table("U+6E05 清 ⿰氵青\nU+8ACB 請 ⿰言青\nU+6674 晴 ⿰日青\nU+6C5F 江 ⿰氵工\nU+6DF8 淸 ⿰氵⿱龶月\n\
+                       U+9752 青 ⿱龶月\nU+975A 靚 ⿰青見\nU+83C1 菁 ⿱艹青\nU+6C34 水 水");
+        assert_eq!(t.minimal_pairs('清'), vec!['晴', '江', '請']);
+        assert_eq!(t.minimal_pairs('晴'), vec!['淸', '清', '請']);
+        assert!(t.minimal_pairs('靚').is_empty());
+        assert!(t.minimal_pairs('水').is_empty());
+    }
+
+    #[test]
+    fn matching_subcomponent_at_depth() {
+        let t = table("U+6674 晴 ⿰日青\nU+9752 青 ⿱龶月\nU+6708 月 月\nU+65E5 日 日\nU+6DF8 淸 ⿰氵⿱龶月");
+        let at = |pattern: &str, depth| t.ids_has_matching_subcomponent_at_depth(t.primary_decomposition('晴').unwrap(), &parse_partial(pattern).unwrap(), '.', depth);
+        // 日 is a child of 晴, 月 a child of its child 青
+        assert!(at("日", Some(1)) && !at("日", Some(2)));
+        assert!(at("月", Some(2)) && !at("月", Some(1)));
+        assert!(at("⿱龶月", Some(1)) && !at("⿱龶月", Some(0)));
+        assert!(at("⿰日.", Some(0)));
+        assert!(!at("⿰日.", Some(1)));
+        assert!(!at("月", Some(3)));
+        assert!(at("月", None) && at("日", None));
+        let ids = parse("⿰氵⿱龶月").unwrap();
+        assert!(t.ids_has_matching_subcomponent_at_depth(&ids, &parse_partial("⿱龶月").unwrap(), '.', Some(1)));
+        assert!(!t.ids_has_matching_subcomponent_at_depth(&ids, &parse_partial("⿱龶月").unwrap(), '.', Some(2)));
+    }
+
+    #[test]
+    fn variation_indicators_match_base_only_when_lenient() {
+        let ids = parse("⿰氵〾青").unwrap();
+        assert_eq!(ids, IDS::Composition {
+            idc: IDC('⿰'),
+            children: vec![IDS::Char('氵'), IDS::VariantOf { base: Box::new(IDS::Char('青')) }],
+        });
+        assert_eq!(ids.to_string(), "⿰氵〾青");
+        assert!(parse("〾").is_err());
+
+        let mut t = table("U+9752 青 ⿱龶月\nU+6708 月 月\nU+6C35 氵 氵\nU+6E05 清 ⿰氵〾青");
+        let stored = t.primary_decomposition('清').unwrap().clone();
+        let partial = |s| parse_partial(s).unwrap();
+        assert!(t.ids_match(&stored, &parse("⿰氵〾青").unwrap(), '.'));
+        assert!(!t.ids_match(&stored, &parse("⿰氵青").unwrap(), '.'));
+        assert!(t.ids_has_subcomponent(&stored, &parse("〾青").unwrap()));
+        assert!(!t.ids_has_subcomponent(&stored, &IDS::Char('青')));
+        assert!(!t.ids_has_subcomponent(&stored, &IDS::Char('月')));
+        assert!(!t.ids_has_matching_subcomponent(&stored, &partial("⿱龶"), '.'));
+        assert!(t.find_leaf_only('月').iter().all(|(k, _)| *k != '清'));
+
+        t.set_lenient_variation_indicators(true);
+        assert!(t.ids_match(&stored, &parse("⿰氵青").unwrap(), '.'));
+        assert!(t.ids_match(&parse("⿰氵青").unwrap(), &stored, '.'));
+        assert!(t.ids_has_subcomponent(&stored, &IDS::Char('月')));
+        assert!(t.ids_has_matching_subcomponent(&stored, &partial("⿱龶"), '.'));
+        assert!(t.find_leaf_only('月').iter().any(|(k, _)| *k == '清'));
+    }
+
+    #[test]
+    fn export_index_maps_atomic_components() {
+        let t = table("U+6C35 氵 氵\nU+9F36 龶 龶\nU+6708 月 月\nU+9752 青 ⿱龶月\nU+6E05 清 ⿰氵青\nU+6709 有 ⿸𠂇月");
+        let index = t.export_index();
+        assert_eq!(index[&'月'], vec!['月', '有', '清', '青']);
+        assert_eq!(index[&'氵'], vec!['氵', '清']);
+        assert_eq!(index[&'𠂇'], vec!['有']);
+        assert!(!index.contains_key(&'青'));
+        assert_eq!(index.len(), 4);
+    }
+
+    #[test]
+    fn export_index_scans_when_the_index_does_not_apply() {
+        let mut t = table("U+6708 月 月\nU+9752 青 ⿱龶月\nU+6E05 清 ⿰氵青\nU+6C34 水 水\nU+6C35 氵 水\nU+E000 \u{E000} ⿰氵月[G] ⿰水月[T]");
+        t.set_preferred_kind(Some(DecompositionKind::Structural));
+        let index = t.export_index();
+        // every character the index lists is one `find` lists
+        for (c, chars) in &index {
+            let mut found: Vec<char> = crate::search_find(&t, &[c.to_string()]).unwrap().into_iter().map(|(k, _)| k).collect();
+            found.dedup();
+            assert_eq!(chars, &found);
+        }
+        assert_eq!(index[&'月'], vec!['月', '清', '青', '\u{E000}']);
+        assert_eq!(index[&'水'], vec!['水', '氵', '清', '\u{E000}']);
+        assert!(!index.contains_key(&'氵'));
+    }
+
+    #[test]
+    fn prune_trivial_keeps_sole_self_entries() {
+        let mut t = table("U+4E85 亅 亅\nU+5B50 子 子\nU+5B50 子 ⿱乛⿻亅一\nU+4E00 一 一\nU+6C34 水 水[G]\nU+6C34 水 水[T]");
+        assert_eq!(t.prune_trivial(), 1);
+        assert_eq!(t.variants('子'), vec![(Tag::Anon(1), parse("⿱乛⿻亅一").unwrap())]);
+        assert_eq!(t.variants('亅'), vec![(Tag::Variant(vec![]), IDS::Char('亅'))]);
+        assert_eq!(t.variants('水').len(), 2);
+        assert_eq!(t.find_leaf_only('亅').into_iter().map(|(k, _)| k).collect::<Vec<_>>(), vec!['亅', '子']);
+        assert_eq!(t.prune_trivial(), 0);
+
+        // 子 keeps ~2 alone, which a new duplicate must not take
+        let mut t = table("U+5B50 子 子 子 ⿱乛⿻亅一");
+        assert_eq!(t.prune_trivial(), 2);
+        assert!(t.insert_entry('子', Tag::Variant(vec![]), IDS::Char('子')));
+        assert!(!t.insert_entry('子', Tag::Variant(vec![]), parse("⿱了一").unwrap()));
+        assert_eq!(t.variants('子'), vec![
+            (Tag::Anon(2), parse("⿱乛⿻亅一").unwrap()),
+            (Tag::Variant(vec![]), IDS::Char('子')),
+            (Tag::Anon(3), parse("⿱了一").unwrap()),
+        ]);
+    }
+
+    #[test]
+    fn from_str_for_ids_and_tag() {
+        assert_eq!("⿰氵青".parse::<IDS>(), parse("⿰氵青"));
+        assert_eq!("⿰氵".parse::<IDS>(), Err(ParseError::MissingComponents { idc: IDC('⿰'), position: 0, expected: 2, found: 1 }));
+        assert_eq!("GTJ".parse::<Tag>(), Ok(Tag::Variant(vec!["G".into(), "T".into(), "J".into()])));
+        assert_eq!("G,UCS2003".parse::<Tag>(), Ok(Tag::Variant(vec!["G".into(), "UCS2003".into()])));
+        assert_eq!("~2".parse::<Tag>(), Ok(Tag::Anon(2)));
+        assert_eq!("".parse::<Tag>(), Ok(Tag::Variant(vec![])));
+        assert_eq!("~x".parse::<Tag>(), Err(ParseError::Invalid { position: 1 }));
+        assert_eq!("[G]".parse::<Tag>(), Err(ParseError::Invalid { position: 0 }));
+        assert_eq!("UCS2003".parse::<Tag>(), Ok(Tag::Variant(vec!["UCS2003".into()])));
+        for tag in [Tag::Anon(3), Tag::Variant(vec!["G".into(), "T".into()]), Tag::Variant(vec!["G".into(), "UCS2003".into()]),
+                    Tag::Variant(vec!["UCS2003".into()]), Tag::Variant(vec!["KP".into()])] {
+            assert_eq!(tag.to_string().parse::<Tag>(), Ok(tag));
+        }
+        assert_eq!(Tag::Variant(vec!["G".into(), "Z".into()]).to_string(), "G,Z");
+    }
+
+    #[test]
+    fn tags_round_trip_through_display() {
+        // codes of region letters and others, one or more characters long
+        const LETTERS: &[char] = &['G', 'T', 'J', 'K', 'P', 'Z', 'B', '2', '0'];
+        let mut rng = crate::SplitMix64(7);
+        for _ in 0..2000 {
+            let mut regions: Vec<String> = vec![];
+            for _ in 0..rng.next() % 4 {
+                let code: String = (0..1 + rng.next() % 3).map(|_| LETTERS[(rng.next() % LETTERS.len() as u64) as usize]).collect();
+                if !regions.contains(&code) {
+                    regions.push(code);
+                }
+            }
+            let tag = Tag::Variant(regions);
+            assert_eq!(tag.to_string().parse::<Tag>(), Ok(tag.clone()), "{}", tag);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn load_file_regions_skips_other_regions() {
+        let path = std::env::temp_dir().join(format!("hanzi-search-regions-{}.txt", std::process::id()));
+        std::fs::write(&path, "U+9AA8 骨 ⿱⿵冂⿰𠃌丄⺼[G] ⿱⿵冂⿰丄𠃌⺼[J]\nU+6E05 清 ⿰氵青 ⿰氵靑[GT]\nU+5B57 字 ⿱宀子[GJ]\nU+6D77 海 ⿰氵每[S]\n").unwrap();
+        let t = IDSTable::load_file_regions(&path, &["J"]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(t.variants('骨'), vec![(Tag::from("J".to_string()), parse("⿱⿵冂⿰丄𠃌⺼").unwrap())]);
+        assert_eq!(t.variants('清'), vec![(Tag::Variant(vec![]), parse("⿰氵青").unwrap())]);
+        assert_eq!(t.variants('字').len(), 1);
+        assert_eq!(t.variants('海').len(), 1);
+        assert_eq!(t.iter().count(), 4);
+    }
+
+    #[test]
+    fn for_each_stops_when_broken() {
+        let t = table("U+6C34 水 水\nU+6C35 氵 水\nU+6E05 清 ⿰氵青\nU+6C5F 江 ⿰氵工\nU+5DE5 工 工");
+        let mut visited = 0;
+        let mut found = None;
+        let flow = t.for_each(|k, _, ids| {
+            visited += 1;
+            if matches!(ids, IDS::Composition { .. }) {
+                found = Some(k);
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        });
+        assert_eq!(flow, ControlFlow::Break(()));
+        assert!(matches!(found, Some('清' | '江')));
+        assert!(visited < 5);
+
+        let mut visited = 0;
+        assert_eq!(t.for_each(|_, _, _| { visited += 1; ControlFlow::Continue(()) }), ControlFlow::Continue(()));
+        assert_eq!(visited, 5);
+    }
+
+    #[test]
+    fn loose_overlay_matches_component_sets() {
+        let mut t = table("U+5FC5 必 ⿻心丿\nU+5FC3 心 心\nU+4E3F 丿 丿\nU+4E58 乘 ⿻禾北\nU+79BE 禾 ⿱丿木\nU+6728 木 木\n\
+                           U+5317 北 北\nU+5269 剩 ⿰乘刂\nU+5202 刂 刂\nU+6C81 沁 ⿰氵心");
+        let pmatch = |t: &IDSTable, k, pattern| t.ids_has_matching_subcomponent(t.primary_decomposition(k).unwrap(), &parse_partial(pattern).unwrap(), '.');
+        assert!(pmatch(&t, '必', "⿻心丿"));
+        assert!(!pmatch(&t, '必', "⿻丿心"));
+        assert!(!pmatch(&t, '乘', "⿻木北"));
+        t.set_loose_overlay(true);
+        assert!(pmatch(&t, '必', "⿻丿心"));
+        assert!(pmatch(&t, '乘', "⿻木北"));
+        assert!(pmatch(&t, '剩', "⿻禾刂"));
+        assert!(pmatch(&t, '剩', "⿻北."));
+        assert!(!pmatch(&t, '沁', "⿻心丿"));
+        assert!(!pmatch(&t, '乘', "⿻木心"));
+        let ids = t.primary_decomposition('剩').unwrap();
+        assert!(t.ids_has_matching_subcomponent_at_depth(ids, &parse_partial("⿻木北").unwrap(), '.', Some(1)));
+        assert!(!t.ids_has_matching_subcomponent_at_depth(ids, &parse_partial("⿻木刂").unwrap(), '.', Some(1)));
+    }
+
+    #[test]
+    fn match_mixed_children() {
+        let t = table("U+597D 好 ⿰女子\nU+59DF 姟 ⿰女亥\nU+4EA5 亥 ⿳亠{亥中}人\nU+5B57 字 ⿱宀子\nU+5973 女 女\nU+5B50 子 子\nU+4EBA 人 人");
+        let matching = |pattern: &str| {
+            let pattern = parse_mixed(pattern).unwrap();
+            let mut found: Vec<char> = t.iter().filter(|(_, ids)| t.match_mixed(ids, &pattern, '.')).map(|((k, _), _)| k).collect();
+            found.sort();
+            found
+        };
+        assert_eq!(parse_mixed("⿰女*子"), Ok(MixedPattern {
+            idc: IDC('⿰'),
+            children: vec![ChildPattern::Exact(IDS::Char('女')), ChildPattern::Contains(IDS::Char('子'))],
+        }));
+        assert_eq!(matching("⿰女."), vec!['好', '姟']);
+        assert_eq!(matching("⿰女"), vec!['好', '姟']);
+        assert_eq!(matching("⿰女*子"), vec!['好']);
+        assert_eq!(matching("⿰女*人"), vec!['姟']);
+        assert_eq!(matching("⿰.*⿳亠."), vec!['姟']);
+        assert_eq!(matching("⿰女亥"), vec!['姟']);
+        assert_eq!(matching("⿱.*子"), vec!['字']);
+        assert!(matching("⿰女*宀").is_empty());
+        assert!(parse_mixed("女子").is_err());
+        assert!(parse_mixed("⿰女子子").is_err());
+    }
+
+    #[test]
+    fn match_mixed_stops_on_cyclic_data() {
+        let t = table("U+E000 \u{E000} \u{E001}\nU+E001 \u{E001} \u{E000}");
+        let pattern = parse_mixed("⿰女.").unwrap();
+        assert!(!t.match_mixed(&IDS::Char('\u{E000}'), &pattern, '.'));
+    }
+
+    #[test]
+    fn tag_for_display_tells_variants_apart() {
+        let t = table("U+6797 林 ⿰木木 ⿰木木 ⿱木木[GT]\nU+6E05 清 ⿰氵青\nU+6C5F 江 ⿰氵工[G] ⿰氵工[G]");
+        let labels = |k| t.variants(k).iter().map(|(tag, _)| t.tag_for_display(k, tag)).collect::<Vec<_>>();
+        assert_eq!(labels('林'), vec!["variant 1", "variant 2", "GT"]);
+        assert_eq!(labels('清'), vec![""]);
+        assert_eq!(labels('江'), vec!["G", "variant 2"]);
+        assert_eq!(t.tag_for_display('水', &Tag::Anon(2)), "variant 3");
+        assert_eq!(t.tag_for_display('不', &Tag::Variant(vec!["KP".into()])), "KP");
+    }
+
+    #[test]
+    fn structural_analogs_vary_one_child() {
+        let t = table("U+6E05 清 ⿰氵青\nU+5029 倩 ⿰亻青\nU+8ACB 請 ⿰言青\nU+6C5F 江 ⿰氵工\nU+4F5C 作 ⿰亻乍\n\
+                       U+548B 咋 ⿰口乍\nU+6D3B 活 ⿰氵舌\nU+8A71 話 ⿰言舌\nU+6DF8 淸 ⿰氵⿱龶月\nU+9752 青 ⿱龶月\n\
+                       U+83C1 菁 ⿱艹青\nU+6C34 水 水");
+        assert_eq!(t.structural_analogs('清', 0), vec!['倩', '請']);
+        assert_eq!(t.structural_analogs('作', 0), vec!['咋']);
+        assert_eq!(t.structural_analogs('活', 0), vec!['話']);
+        // 淸 writes 青 out, so its right side is no different
+        assert_eq!(t.structural_analogs('清', 1), vec!['江', '活']);
+        assert!(t.structural_analogs('清', 2).is_empty());
+        assert!(t.structural_analogs('水', 0).is_empty());
+    }
+
+    #[test]
+    fn standard_idcs() {
+        let arities = [
+            ('⿰', 2), ('⿱', 2), ('⿲', 3), ('⿳', 3), ('⿴', 2), ('⿵', 2), ('⿶', 2), ('⿷', 2),
+            ('⿸', 2), ('⿹', 2), ('⿺', 2), ('⿻', 2), ('⿼', 2), ('⿽', 2), ('⿾', 1), ('⿿', 1), ('㇯', 2),
+        ];
+        for (c, arity) in arities {
+            assert_eq!(IDC::new(c).map(IDC::arity), Some(arity), "{}", c);
+            let ids: String = core::iter::once(c).chain("口日木".chars().take(arity)).collect();
+            assert_eq!(parse(&ids).map(|ids| ids.to_string()), Ok(ids.clone()));
+            assert!(parse(&ids[..ids.len() - '口'.len_utf8()]).is_err(), "{}", ids);
+        }
+        assert_eq!((0x2FF0..=0x2FFF).filter_map(char::from_u32).filter(|&c| is_idc(c)).count(), 16);
+        // just outside the IDC block, and the stroke before ㇯
+        assert!(IDC::new('\u{2FEF}').is_none() && IDC::new('\u{3000}').is_none() && IDC::new('\u{31EE}').is_none());
+        assert_eq!(parse("⿾⿰口木"), Ok(IDS::Composition { idc: IDC('⿾'), children: vec![parse("⿰口木").unwrap()] }));
+    }
 
     #[test]
     fn parse_ids_special() {
@@ -429,12 +4057,37 @@ mod tests {
         assert_eq!(ids, IDS::Char('啊'));
     }
 
+    #[test]
+    fn parse_ids_entity() {
+        let ids = parse("⿰氵&CDP-8BF1;").unwrap();
+        assert_eq!(ids, IDS::Composition {
+            idc: IDC::new('⿰').unwrap(),
+            children: vec![
+                IDS::Char('氵'),
+                IDS::Entity("CDP-8BF1".to_string()),
+            ],
+        });
+        assert_eq!(ids.to_string(), "⿰氵&CDP-8BF1;");
+        let tids = parse_tagged("⿱&GT-12345;&U-i001+5F50;[G]").unwrap();
+        assert_eq!(tids.ids.to_string(), "⿱&GT-12345;&U-i001+5F50;");
+        assert_eq!(parse("&").unwrap(), IDS::Char('&'));
+    }
+
+    #[test]
+    fn entity_components_match_by_name() {
+        let t = table("U+6C35 氵 氵\nU+2A6D6 𪛖 ⿰氵&CDP-8BF1;");
+        let needle = IDS::Entity("CDP-8BF1".to_string());
+        assert!(t.ids_has_subcomponent(&IDS::Char('𪛖'), &needle));
+        assert!(!t.ids_has_subcomponent(&IDS::Char('𪛖'), &IDS::Entity("CDP-8BF2".to_string())));
+        assert!(t.ids_match(&IDS::Char('𪛖'), &parse("⿰.&CDP-8BF1;").unwrap(), '.'));
+    }
+
     #[test]
     fn parse_tagged_ids_char() {
         let input = "啊";
         let (_, ids) = parser_tagged_ids(input).unwrap();
         assert_eq!(ids, TaggedIDS {
-            tag: Tag::Variant("".to_string()),
+            tag: Tag::Variant(vec![]),
             ids: IDS::Char('啊'),
         });
     }
@@ -451,7 +4104,7 @@ mod tests {
                     IDS::Special("⻞下".to_string()),
                 ],
             },
-            tag: Tag::Variant("G".to_string())
+            tag: Tag::Variant(vec!["G".to_string()])
         });
     }
 }
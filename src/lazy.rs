@@ -0,0 +1,155 @@
+//! Opening a data file without parsing it up front.
+//!
+//! `IDSTable::open_lazy` maps the file into memory and reads only the
+//! character and tags of each line, so that the `tags` index expansion
+//! relies on is complete. A decomposition is parsed the first time it is
+//! used and then kept. Such a table is searched like any other, by
+//! scanning: the component index needs every entry, so the first call of a
+//! method changing the table (or of `IDSTable::load_all`) parses the rest
+//! and builds it.
+
+use std::{fs::File, io, path::Path, sync::Arc};
+
+use crate::ids::IDSTable;
+
+impl IDSTable {
+    /// Opens `path`, in the layout `load_file` reads, parsing each
+    /// decomposition only when it is first used. The file must not change
+    /// while the table is open.
+    pub fn open_lazy<P: AsRef<Path>>(path: P) -> io::Result<IDSTable> {
+        Ok(IDSTable::from_lazy_text(Arc::new(MappedText::open(path)?)))
+    }
+}
+
+/// A data file mapped read-only into memory, checked to be UTF-8.
+#[cfg(unix)]
+struct MappedText {
+    ptr: *const u8,
+    len: usize,
+}
+
+// the mapping is never written to
+#[cfg(unix)]
+unsafe impl Send for MappedText {}
+#[cfg(unix)]
+unsafe impl Sync for MappedText {}
+
+#[cfg(unix)]
+impl MappedText {
+    fn open<P: AsRef<Path>>(path: P) -> io::Result<MappedText> {
+        use std::os::fd::AsRawFd;
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len()).map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        if len == 0 {
+            // an empty mapping is not allowed
+            return Ok(MappedText { ptr: core::ptr::NonNull::dangling().as_ptr(), len });
+        }
+        // SAFETY: a fresh private read-only mapping of the whole file; the
+        // descriptor may be closed once it exists
+        let ptr = unsafe { libc::mmap(core::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let text = MappedText { ptr: ptr as *const u8, len };
+        if let Err(e) = std::str::from_utf8(text.bytes()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+        Ok(text)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: `ptr` points to `len` mapped bytes until `drop`
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl AsRef<str> for MappedText {
+    fn as_ref(&self) -> &str {
+        // SAFETY: checked in `open`
+        unsafe { std::str::from_utf8_unchecked(self.bytes()) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for MappedText {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: unmaps what `open` mapped, which nothing borrows any more
+            unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+        }
+    }
+}
+
+/// Elsewhere the file is read into memory instead.
+#[cfg(not(unix))]
+struct MappedText(String);
+
+#[cfg(not(unix))]
+impl MappedText {
+    fn open<P: AsRef<Path>>(path: P) -> io::Result<MappedText> {
+        Ok(MappedText(std::fs::read_to_string(path)?))
+    }
+}
+
+#[cfg(not(unix))]
+impl AsRef<str> for MappedText {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ids::{Tag, parse}, search_find, search_match, search_pmatch};
+
+    const DATA: &str = "U+6728\t木\t木\r\nU+6797\t林\t⿰木木\t⿱木木[J]\r\nU+68EE\t森\t⿱木林\r\nU+6E05\t清\t⿰氵青\r\nU+9752\t青\t⿱龶月\t⿱{\r\n";
+
+    fn open(name: &str, data: &str) -> IDSTable {
+        let path = std::env::temp_dir().join(format!("hanzi-search-lazy-{}-{}.txt", name, std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        let table = IDSTable::open_lazy(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        table
+    }
+
+    #[test]
+    fn parses_only_what_is_used() {
+        let t = open("used", DATA);
+        assert_eq!(t.len(), 7);
+        assert!(t.contains_char('清'));
+        let before = t.approximate_memory_bytes();
+        assert_eq!(t.variants('林'), vec![
+            (Tag::Variant(vec![]), parse("⿰木木").unwrap()),
+            (Tag::from("J".to_string()), parse("⿱木木").unwrap()),
+        ]);
+        assert!(t.approximate_memory_bytes() > before);
+        assert!(t.variants('鬱').is_empty());
+    }
+
+    #[test]
+    fn searches_like_a_loaded_table() {
+        let lazy = open("search", DATA);
+        let loaded = IDSTable::load_from_string(DATA).unwrap();
+        let needles = ["木".to_string()];
+        assert_eq!(search_find(&lazy, &needles), search_find(&loaded, &needles));
+        assert_eq!(search_match(&lazy, "⿱木."), search_match(&loaded, "⿱木."));
+        assert_eq!(search_pmatch(&lazy, "⿱龶."), search_pmatch(&loaded, "⿱龶."));
+        assert_eq!(lazy.export_index(), loaded.export_index());
+        // the decomposition that does not parse is left out as loading leaves it out
+        assert_eq!(lazy.primary_decomposition('青'), loaded.primary_decomposition('青'));
+        assert_eq!(lazy.iter().count(), loaded.iter().count());
+    }
+
+    #[test]
+    fn changing_the_table_parses_the_rest() {
+        let mut t = open("change", DATA);
+        t.load_all();
+        assert_eq!(t.len(), 6);
+        assert_eq!(t.variants('青').len(), 1);
+        t.retain_reachable(&['森']);
+        assert_eq!(t.iter().count(), 4);
+        assert_eq!(t.find_leaf_only('木').len(), 4);
+    }
+}
@@ -1,43 +1,287 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod format;
 pub mod ids;
+#[cfg(feature = "std")]
+mod lazy;
+pub mod normalize;
+#[cfg(feature = "python")]
+pub mod py;
+pub mod query;
+
+use alloc::{borrow::Cow, format, string::String, vec, vec::Vec};
 
-use ids::{IDSTable, Tag, parse};
+use ids::{IDSTable, IDS, SearchMetrics, Tag, parse, parse_mixed, parse_partial};
 
 const WILDCARD_CHAR: char = '.';
 
 // Shared search functions used by both CLI and WASM
 
+/// `query` as it should be parsed against `table`.
+fn prepare<'a>(table: &IDSTable, query: &'a str) -> Cow<'a, str> {
+    if table.normalizes() {
+        Cow::Owned(normalize::normalize(query))
+    } else {
+        Cow::Borrowed(query)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FindOptions {
+    /// Whether a character that is itself one of the needles (e.g. 口 for
+    /// `find 口`) is part of the result.
+    pub include_self: bool,
+    /// Whether one needle suffices, instead of requiring all of them.
+    pub any: bool,
+}
+
+impl Default for FindOptions {
+    fn default() -> Self {
+        FindOptions { include_self: true, any: false }
+    }
+}
+
 pub fn search_find(table: &IDSTable, needle_strs: &[String]) -> Result<Vec<(char, Tag)>, String> {
-    let needles = {
-        let mut needles = vec![];
-        for needle_str in needle_strs {
-            let needle = parse(needle_str)
-                .map_err(|_| format!("Cannot parse needle {}", needle_str))?;
-            needles.push(needle);
+    search_find_with(table, needle_strs, &FindOptions::default())
+}
+
+/// Characters containing at least one of the needles.
+pub fn search_find_any(table: &IDSTable, needle_strs: &[String]) -> Result<Vec<(char, Tag)>, String> {
+    search_find_with(table, needle_strs, &FindOptions { any: true, ..FindOptions::default() })
+}
+
+/// A needle written with a leading `-` or `!`, as in `find 水 -口`, must
+/// not be contained in the result instead.
+pub fn search_find_with(table: &IDSTable, needle_strs: &[String], options: &FindOptions) -> Result<Vec<(char, Tag)>, String> {
+    search_find_metered(table, needle_strs, options).map(|(result, _)| result)
+}
+
+/// `search_find_with` together with how much work it took. Only
+/// `candidates_scanned` is counted: the entries the component index lists
+/// when it can be used, all of them otherwise.
+pub fn search_find_metered(table: &IDSTable, needle_strs: &[String], options: &FindOptions) -> Result<(Vec<(char, Tag)>, SearchMetrics), String> {
+    let needles = parse_needles(table, needle_strs)?;
+    let mut metrics = SearchMetrics::default();
+    let result = find_needles(table, &needles, options, &mut metrics);
+    Ok((result, metrics))
+}
+
+/// The component to look up in the index instead of scanning the table.
+fn indexed_needle(table: &IDSTable, needles: &Needles) -> Option<char> {
+    match needles.include.as_slice() {
+        [IDS::Char(c)] if table.index_applies() => Some(*c),
+        _ => None,
+    }
+}
+
+fn find_needles(table: &IDSTable, needles: &Needles, options: &FindOptions, metrics: &mut SearchMetrics) -> Vec<(char, Tag)> {
+    if let Some(c) = indexed_needle(table, needles) {
+        let mut result = table.find_leaf_only(c);
+        metrics.candidates_scanned += result.len();
+        if !options.include_self || !needles.exclude.is_empty() {
+            result.retain(|(k, t)| table.decomposition(*k, t).is_some_and(|ids| {
+                (options.include_self || !is_self(&needles.include, *k, ids)) && !needles.excluded(table, ids)
+            }));
         }
-        needles
-    };
+        return result;
+    }
 
+    metrics.candidates_scanned += table.len();
     let mut result: Vec<(char, Tag)> = table.iter()
         .filter_map(|((k, t), ids)| {
-            if needles.iter().all(|needle| table.ids_has_subcomponent(&ids, &needle)) {
-                Some((*k, t.clone()))
+            if find_hit(table, needles, options, k, ids) {
+                Some((k, t.clone()))
             } else {
                 None
             }
         })
         .collect();
     result.sort();
+    result
+}
+
+/// Narrows the result of `find` for `previous_needles`, whose characters
+/// were `previous`, by one more needle, without scanning the whole table:
+/// the result is that of `find` with all the needles, and only the variants
+/// of `previous` are tested.
+pub fn search_find_refine(table: &IDSTable, previous: &[char], previous_needles: &[String], needle_str: &str) -> Result<Vec<(char, Tag)>, String> {
+    let mut needle_strs = previous_needles.to_vec();
+    needle_strs.push(String::from(needle_str));
+    let needles = parse_needles(table, &needle_strs)?;
+    let options = FindOptions::default();
+    let mut result = table.search_find_within(previous, &[]);
+    result.retain(|(k, t)| table.decomposition(*k, t).is_some_and(|ids| find_hit(table, &needles, &options, *k, ids)));
     Ok(result)
 }
 
-pub fn search_match(table: &IDSTable, pattern_str: &str) -> Result<Vec<(char, Tag)>, String> {
-    let pattern = parse(pattern_str)
-        .map_err(|_| format!("Cannot parse pattern {}", pattern_str))?;
+/// Characters whose variants, taken together, contain every needle: each
+/// needle may come from a different variant. Every character is listed once.
+/// A negated needle leaves out characters having it in any variant.
+pub fn search_find_char_level(table: &IDSTable, needle_strs: &[String]) -> Result<Vec<char>, String> {
+    let needles = parse_needles(table, needle_strs)?;
+    let chars_with = |needle: &IDS| {
+        let single = Needles { include: vec![needle.clone()], exclude: vec![] };
+        let mut chars: Vec<char> = find_needles(table, &single, &FindOptions::default(), &mut SearchMetrics::default()).into_iter().map(|(k, _)| k).collect();
+        chars.sort_unstable();
+        chars.dedup();
+        chars
+    };
+    let mut result: Option<Vec<char>> = None;
+    for needle in &needles.include {
+        let chars = chars_with(needle);
+        result = Some(match result {
+            Some(previous) => previous.into_iter().filter(|k| chars.binary_search(k).is_ok()).collect(),
+            None => chars,
+        });
+    }
+    // with only negated needles every character is a candidate
+    let mut result = result.unwrap_or_else(|| {
+        let mut chars: Vec<char> = table.iter().map(|((k, _), _)| k).collect();
+        chars.sort_unstable();
+        chars.dedup();
+        chars
+    });
+    for needle in &needles.exclude {
+        let chars = chars_with(needle);
+        result.retain(|k| chars.binary_search(k).is_err());
+    }
+    Ok(result)
+}
+
+/// The needles of a `find`: those to be contained, and those to be absent.
+struct Needles {
+    include: Vec<IDS>,
+    exclude: Vec<IDS>,
+}
+
+impl Needles {
+    fn excluded(&self, table: &IDSTable, ids: &IDS) -> bool {
+        self.exclude.iter().any(|needle| table.ids_has_subcomponent(ids, needle))
+    }
+}
+
+const NEGATION_PREFIXES: [char; 2] = ['-', '!'];
+
+fn parse_needles(table: &IDSTable, needle_strs: &[String]) -> Result<Needles, String> {
+    let mut needles = Needles { include: vec![], exclude: vec![] };
+    for needle_str in needle_strs {
+        let prepared = prepare(table, needle_str);
+        let parsed = parse(&prepared);
+        // a needle that parses as it is, such as a lone `-`, is not negated
+        let negated = prepared.strip_prefix(NEGATION_PREFIXES).filter(|rest| parsed.is_err() && !rest.is_empty());
+        match negated {
+            Some(rest) => needles.exclude.push(parse(rest).map_err(|e| format!("Cannot parse needle {}: {}", needle_str, e))?),
+            None => needles.include.push(parsed.map_err(|e| format!("Cannot parse needle {}: {}", needle_str, e))?),
+        }
+    }
+    Ok(needles)
+}
+
+fn is_self(needles: &[IDS], k: char, ids: &IDS) -> bool {
+    needles.iter().any(|needle| needle == &IDS::Char(k) || needle == ids)
+}
+
+/// Whether the entry `k` decomposed as `ids` belongs in a `find` result.
+fn find_hit(table: &IDSTable, needles: &Needles, options: &FindOptions, k: char, ids: &IDS) -> bool {
+    if !options.include_self && is_self(&needles.include, k, ids) {
+        return false;
+    }
+    let contains = |needle: &IDS| table.ids_has_subcomponent(ids, needle);
+    let included = if options.any { needles.include.iter().any(contains) } else { needles.include.iter().all(contains) };
+    included && !needles.excluded(table, ids)
+}
+
+/// The characters matched by a boolean query such as `(水 AND 青) OR 門`;
+/// see the `query` module.
+pub fn search_query(table: &IDSTable, expr_str: &str) -> Result<Vec<char>, String> {
+    let expr = query::parse_query(&prepare(table, expr_str))
+        .map_err(|e| format!("Cannot parse query {}: {}", expr_str, e))?;
+    Ok(table.evaluate(&expr))
+}
+
+/// Result of a search that may have been cut short by its time budget.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedSearch {
+    /// Sorted matches among the entries scanned before stopping
+    pub results: Vec<(char, Tag)>,
+    pub timed_out: bool,
+}
+
+/// How many entries are tested between two looks at the clock.
+#[cfg(feature = "std")]
+const TIMEOUT_CHECK_INTERVAL: usize = 256;
+
+#[cfg(feature = "std")]
+fn scan_with_budget(table: &IDSTable, budget: std::time::Duration, hit: impl Fn(char, &IDS) -> bool) -> TimedSearch {
+    let start = std::time::Instant::now();
+    let mut search = TimedSearch { results: vec![], timed_out: false };
+    for (i, ((k, t), ids)) in table.iter().enumerate() {
+        if i % TIMEOUT_CHECK_INTERVAL == 0 && start.elapsed() >= budget {
+            search.timed_out = true;
+            break;
+        }
+        if hit(k, ids) {
+            search.results.push((k, t.clone()));
+        }
+    }
+    search.results.sort();
+    search
+}
+
+/// Like `search_find_with`, but stops scanning once `budget` has passed.
+#[cfg(feature = "std")]
+pub fn search_find_with_timeout(table: &IDSTable, needle_strs: &[String], options: &FindOptions, budget: std::time::Duration) -> Result<TimedSearch, String> {
+    let needles = parse_needles(table, needle_strs)?;
+    Ok(scan_with_budget(table, budget, |k, ids| find_hit(table, &needles, options, k, ids)))
+}
+
+/// Like `search_match`, but stops scanning once `budget` has passed.
+#[cfg(feature = "std")]
+pub fn search_match_with_timeout(table: &IDSTable, pattern_str: &str, budget: std::time::Duration) -> Result<TimedSearch, String> {
+    let pattern = MatchPattern::parse(table, pattern_str)?;
+    Ok(scan_with_budget(table, budget, |_, ids| pattern.matches(table, ids)))
+}
+
+/// Characters having every needle among the top-level parts of their
+/// primary decomposition.
+pub fn search_find_direct(table: &IDSTable, needle_strs: &[String]) -> Result<Vec<(char, Tag)>, String> {
+    let needles = parse_needles(table, needle_strs)?;
+    let mut result: Vec<(char, Tag)> = table.iter()
+        .filter(|((k, t), _)| table.primary_tag(*k) == Some(t))
+        .filter(|((k, _), _)| needles.include.iter().all(|needle| table.has_direct_component(*k, needle)))
+        .filter(|((k, _), _)| !needles.exclude.iter().any(|needle| table.has_direct_component(*k, needle)))
+        .map(|((k, t), _)| (k, t.clone()))
+        .collect();
+    result.sort();
+    Ok(result)
+}
+
+/// Whether `xs` holds exactly the elements of `ys`, counting repeats.
+fn same_multiset(mut xs: Vec<IDS>, ys: &[IDS]) -> bool {
+    for y in ys {
+        let Some(i) = xs.iter().position(|x| x == y) else {
+            return false;
+        };
+        xs.swap_remove(i);
+    }
+    xs.is_empty()
+}
+
+pub fn search_find_exact(table: &IDSTable, needle_strs: &[String]) -> Result<Vec<(char, Tag)>, String> {
+    let mut expected: Vec<IDS> = vec![];
+    for needle_str in needle_strs {
+        let needle = parse(&prepare(table, needle_str))
+            .map_err(|e| format!("Cannot parse needle {}: {}", needle_str, e))?;
+        expected.extend(table.atomic_leaves(&needle));
+    }
 
-    let mut result: Vec<(char, Tag) > = table.iter()
+    let mut result: Vec<(char, Tag)> = table.iter()
         .filter_map(|((k, t), ids)| {
-            if table.ids_match(ids, &pattern, WILDCARD_CHAR) {
-                Some((*k, t.clone()))
+            if same_multiset(table.atomic_leaves(ids), &expected) {
+                Some((k, t.clone()))
             } else {
                 None
             }
@@ -47,14 +291,115 @@ pub fn search_match(table: &IDSTable, pattern_str: &str) -> Result<Vec<(char, Ta
     Ok(result)
 }
 
-pub fn search_pmatch(table: &IDSTable, pattern_str: &str) -> Result<Vec<(char, Tag)>, String> {
-    let pattern = parse(pattern_str)
-        .map_err(|_| format!("Cannot parse pattern {}", pattern_str))?;
+/// Entries containing the un-encoded component written `{text}` in the
+/// data. The braces may be included in `text` or left out.
+pub fn search_find_special(table: &IDSTable, text: &str) -> Result<Vec<(char, Tag)>, String> {
+    let text = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')).unwrap_or(text);
+    if text.is_empty() {
+        return Err(String::from("Empty special component"));
+    }
+    let needle = IDS::Special(String::from(text));
+    let mut result: Vec<(char, Tag)> = table.iter()
+        .filter(|(_, ids)| table.ids_has_subcomponent(ids, &needle))
+        .map(|((k, t), _)| (k, t.clone()))
+        .collect();
+    result.sort();
+    Ok(result)
+}
 
-    let mut result: Vec<_> = table.iter()
+pub fn search_match(table: &IDSTable, pattern_str: &str) -> Result<Vec<(char, Tag)>, String> {
+    let mut result: Vec<(char, Tag)> = search_match_iter(table, pattern_str)?.collect();
+    result.sort();
+    Ok(result)
+}
+
+/// `search_match` yielding matches as the table scan finds them, unsorted.
+/// A trailing `@REGION`, as in `⿰氵青@J`, expands components with their
+/// variants of that region only.
+pub fn search_match_iter<'a>(table: &'a IDSTable, pattern_str: &str) -> Result<impl Iterator<Item = (char, Tag)> + 'a, String> {
+    let pattern = MatchPattern::parse(table, pattern_str)?;
+    Ok(table.iter()
+        .filter(move |(_, ids)| pattern.matches(table, ids))
+        .map(|((k, t), _)| (k, t.clone())))
+}
+
+/// Characters matching a mixed pattern such as `⿰女*子`, where each child
+/// is matched whole, is `.` for any part, or after `*` only has to be
+/// contained in its part. See `ids::parse_mixed`.
+pub fn search_match_mixed(table: &IDSTable, pattern_str: &str) -> Result<Vec<(char, Tag)>, String> {
+    let pattern = parse_mixed(&prepare(table, pattern_str))
+        .map_err(|e| format!("Cannot parse pattern {}: {}", pattern_str, e))?;
+    let mut result: Vec<(char, Tag)> = table.iter()
+        .filter(|(_, ids)| table.match_mixed(ids, &pattern, WILDCARD_CHAR))
+        .map(|((k, t), _)| (k, t.clone()))
+        .collect();
+    result.sort();
+    Ok(result)
+}
+
+/// A pattern of the `match` searches, which may end in `@REGION`.
+struct MatchPattern {
+    ids: IDS,
+    region: Option<String>,
+}
+
+impl MatchPattern {
+    fn parse(table: &IDSTable, pattern_str: &str) -> Result<MatchPattern, String> {
+        let (pattern, region) = split_region(pattern_str);
+        let ids = parse_partial(&prepare(table, pattern))
+            .map_err(|e| format!("Cannot parse pattern {}: {}", pattern_str, e))?;
+        Ok(MatchPattern { ids, region: region.map(String::from) })
+    }
+
+    fn matches(&self, table: &IDSTable, ids: &IDS) -> bool {
+        self.matches_metered(table, ids, &mut SearchMetrics::default())
+    }
+
+    fn matches_metered(&self, table: &IDSTable, ids: &IDS, metrics: &mut SearchMetrics) -> bool {
+        match &self.region {
+            Some(region) => table.ids_match_in_region_metered(ids, &self.ids, WILDCARD_CHAR, region, metrics),
+            None => table.ids_match_metered(ids, &self.ids, WILDCARD_CHAR, metrics),
+        }
+    }
+}
+
+/// Splits a trailing `@REGION` off a pattern.
+fn split_region(pattern_str: &str) -> (&str, Option<&str>) {
+    match pattern_str.rsplit_once('@') {
+        Some((pattern, region)) if !region.is_empty() && region.chars().all(|c| c.is_ascii_alphanumeric()) => (pattern, Some(region)),
+        _ => (pattern_str, None),
+    }
+}
+
+/// `search_match` together with how much work it took.
+pub fn search_match_metered(table: &IDSTable, pattern_str: &str) -> Result<(Vec<(char, Tag)>, SearchMetrics), String> {
+    let pattern = MatchPattern::parse(table, pattern_str)?;
+
+    let mut metrics = SearchMetrics::default();
+    let mut result = vec![];
+    for ((k, t), ids) in table.iter() {
+        metrics.candidates_scanned += 1;
+        if pattern.matches_metered(table, ids, &mut metrics) {
+            result.push((k, t.clone()));
+        }
+    }
+    result.sort();
+    Ok((result, metrics))
+}
+
+pub fn search_match_any(table: &IDSTable, pattern_strs: &[String]) -> Result<Vec<(char, Tag)>, String> {
+    let patterns = {
+        let mut patterns = vec![];
+        for pattern_str in pattern_strs {
+            patterns.push(MatchPattern::parse(table, pattern_str)?);
+        }
+        patterns
+    };
+
+    let mut result: Vec<(char, Tag)> = table.iter()
         .filter_map(|((k, t), ids)| {
-            if table.ids_has_matching_subcomponent(&ids, &pattern, WILDCARD_CHAR) {
-                Some((*k, t.clone()))
+            if patterns.iter().any(|pattern| pattern.matches(table, ids)) {
+                Some((k, t.clone()))
             } else {
                 None
             }
@@ -65,65 +410,671 @@ pub fn search_pmatch(table: &IDSTable, pattern_str: &str) -> Result<Vec<(char, T
     Ok(result)
 }
 
+pub fn search_pmatch(table: &IDSTable, pattern_str: &str) -> Result<Vec<(char, Tag)>, String> {
+    search_pmatch_at_depth(table, pattern_str, None)
+}
+
+/// `search_pmatch` yielding matches as the table scan finds them, unsorted.
+pub fn search_pmatch_iter<'a>(table: &'a IDSTable, pattern_str: &str) -> Result<impl Iterator<Item = (char, Tag)> + 'a, String> {
+    search_pmatch_at_depth_iter(table, pattern_str, None)
+}
+
+/// `search_pmatch` only counting matches `depth` levels below the root of
+/// each decomposition, see `IDSTable::ids_has_matching_subcomponent_at_depth`.
+pub fn search_pmatch_at_depth(table: &IDSTable, pattern_str: &str, depth: Option<usize>) -> Result<Vec<(char, Tag)>, String> {
+    // like find and match, every matching variant is listed: see `unique_chars`
+    let mut result: Vec<(char, Tag)> = search_pmatch_at_depth_iter(table, pattern_str, depth)?.collect();
+    result.sort();
+    Ok(result)
+}
+
+/// `search_pmatch_at_depth` yielding matches as the table scan finds
+/// them, unsorted.
+pub fn search_pmatch_at_depth_iter<'a>(table: &'a IDSTable, pattern_str: &str, depth: Option<usize>) -> Result<impl Iterator<Item = (char, Tag)> + 'a, String> {
+    let pattern = parse_partial(&prepare(table, pattern_str))
+        .map_err(|_| format!("Cannot parse pattern {}", pattern_str))?;
+    Ok(table.iter()
+        .filter(move |(_, ids)| table.ids_has_matching_subcomponent_at_depth(ids, &pattern, WILDCARD_CHAR, depth))
+        .map(|((k, t), _)| (k, t.clone())))
+}
+
+/// Characters containing `component`, grouped by how many times it occurs
+/// in them (in the variant where it occurs most), the most first.
+pub fn search_occurrences(table: &IDSTable, component: &str) -> Result<Vec<(usize, Vec<char>)>, String> {
+    let needle = parse(&prepare(table, component))
+        .map_err(|e| format!("Cannot parse needle {}: {}", component, e))?;
+    // count in the variants that contain it, keeping each character's most
+    let mut counts: Vec<(char, usize)> = vec![];
+    for (k, t) in search_find(table, &[String::from(component)])? {
+        let count = table.decomposition(k, &t).map_or(0, |ids| table.subcomponent_count(ids, &needle));
+        match counts.last_mut() {
+            Some((last, most)) if *last == k => *most = (*most).max(count),
+            _ => counts.push((k, count)),
+        }
+    }
+    let mut groups: Vec<(usize, Vec<char>)> = vec![];
+    for (k, count) in counts {
+        match groups.iter_mut().find(|(n, _)| *n == count) {
+            Some((_, chars)) => chars.push(k),
+            None => groups.push((count, vec![k])),
+        }
+    }
+    groups.sort_by(|(a, _), (b, _)| b.cmp(a));
+    Ok(groups)
+}
+
+/// Keeps one result per character, the one with the smallest tag.
+pub fn unique_chars(results: &mut Vec<(char, Tag)>) {
+    results.sort();
+    results.dedup_by_key(|(k, _)| *k);
+}
+
+/// Picks `n` of `results` at random, without replacement. The same `seed`
+/// gives the same picks.
+pub fn sample_results(mut results: Vec<(char, Tag)>, n: usize, seed: u64) -> Result<Vec<(char, Tag)>, String> {
+    if n > results.len() {
+        return Err(format!("Cannot pick {} of {} results", n, results.len()));
+    }
+    let mut rng = SplitMix64(seed);
+    for i in 0..n {
+        let j = i + (rng.next() % (results.len() - i) as u64) as usize;
+        results.swap(i, j);
+    }
+    results.truncate(n);
+    Ok(results)
+}
+
+// Small seedable generator, good enough for sampling
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Codepoint,
+    /// Fewest strokes first; needs stroke data loaded into the table
+    Strokes,
+    /// Shallowest decomposition first
+    Depth,
+    /// Most frequent first; needs frequency data loaded into the table
+    Frequency,
+    /// Fewest leaves in the primary decomposition first
+    Simplest,
+}
+
+impl core::str::FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "codepoint" => Ok(SortKey::Codepoint),
+            "strokes" => Ok(SortKey::Strokes),
+            "depth" => Ok(SortKey::Depth),
+            "frequency" => Ok(SortKey::Frequency),
+            "simplest" => Ok(SortKey::Simplest),
+            _ => Err(format!("Unknown sort key {}", s)),
+        }
+    }
+}
+
+/// Reorders search results by `key`. Characters without the needed data
+/// come last; ties are broken by code point.
+pub fn sort_results(table: &IDSTable, result: &mut [(char, Tag)], key: SortKey) {
+    match key {
+        SortKey::Codepoint => result.sort(),
+        SortKey::Strokes => result.sort_by_cached_key(|(k, t)| {
+            (table.stroke_count(*k).unwrap_or(u32::MAX), *k, t.clone())
+        }),
+        SortKey::Depth => result.sort_by_cached_key(|(k, t)| {
+            (table.decomposition(*k, t).map_or(usize::MAX, IDS::depth), *k, t.clone())
+        }),
+        SortKey::Frequency => result.sort_by_cached_key(|(k, t)| {
+            (core::cmp::Reverse(table.frequency(*k)), *k, t.clone())
+        }),
+        SortKey::Simplest => result.sort_by_cached_key(|(k, t)| {
+            (table.primary_decomposition(*k).map_or(usize::MAX, IDS::leaf_count), *k, t.clone())
+        }),
+    }
+}
+
+// Detailed variants returning the matched decomposition alongside each result
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub character: char,
+    pub tag: Tag,
+    pub ids: IDS,
+}
+
+/// The entries of `result` with their decompositions, as formatters take
+/// them.
+pub fn with_ids(table: &IDSTable, result: &[(char, Tag)]) -> Vec<SearchMatch> {
+    result.iter()
+        .filter_map(|(character, tag)| {
+            let ids = table.decomposition(*character, tag)?.clone();
+            Some(SearchMatch { character: *character, tag: tag.clone(), ids })
+        })
+        .collect()
+}
+
+pub fn search_find_detailed(table: &IDSTable, needle_strs: &[String]) -> Result<Vec<SearchMatch>, String> {
+    Ok(with_ids(table, &search_find(table, needle_strs)?))
+}
+
+pub fn search_match_detailed(table: &IDSTable, pattern_str: &str) -> Result<Vec<SearchMatch>, String> {
+    Ok(with_ids(table, &search_match(table, pattern_str)?))
+}
+
+pub fn search_pmatch_detailed(table: &IDSTable, pattern_str: &str) -> Result<Vec<SearchMatch>, String> {
+    Ok(with_ids(table, &search_pmatch(table, pattern_str)?))
+}
+
+/// Every variant of each character of `chars`, in order. Whitespace and
+/// characters without an entry are skipped.
+pub fn decompose(table: &IDSTable, chars: &str) -> Vec<SearchMatch> {
+    chars.chars()
+        .filter(|k| !k.is_whitespace())
+        .flat_map(|character| {
+            table.variants(character).into_iter()
+                .map(move |(tag, ids)| SearchMatch { character, tag, ids })
+        })
+        .collect()
+}
+
 // WASM-specific code
 #[cfg(target_arch = "wasm32")]
 mod wasm {
+    use std::cell::RefCell;
+
     use wasm_bindgen::prelude::*;
     use serde::{Deserialize, Serialize};
-    use crate::ids::IDSTable;
+    use crate::ids::{IDSTable, Tag};
 
     const CHAI_DATA: &str = include_str!("../chai.txt");
 
     #[derive(Serialize, Deserialize)]
     pub struct SearchResult {
+        pub ok: bool,
+        pub error: Option<String>,
         pub results: Vec<String>,
     }
 
-    fn get_table() -> IDSTable {
-        IDSTable::load_from_string(CHAI_DATA).expect("Failed to load embedded data")
+    impl From<Result<Vec<(char, Tag)>, String>> for SearchResult {
+        fn from(result: Result<Vec<(char, Tag)>, String>) -> Self {
+            match result {
+                Ok(tchars) => {
+                    let mut results: Vec<String> = tchars.iter().map(|(c, _t)| format!("{}", c)).collect();
+                    results.dedup();
+                    SearchResult { ok: true, error: None, results }
+                }
+                Err(e) => SearchResult { ok: false, error: Some(e), results: vec![] },
+            }
+        }
+    }
+
+    thread_local! {
+        // Loaded on first use and kept, so that a call only searches
+        static TABLE: IDSTable = IDSTable::load_from_string(CHAI_DATA).expect("Failed to load embedded data");
+    }
+
+    fn with_table<R>(f: impl FnOnce(&IDSTable) -> R) -> R {
+        TABLE.with(f)
+    }
+
+    fn to_js(result: SearchResult) -> JsValue {
+        serde_wasm_bindgen::to_value(&result).unwrap()
+    }
+
+    #[derive(Serialize)]
+    pub struct Decomposition {
+        pub character: String,
+        pub tag: String,
+        pub ids: String,
+    }
+
+    /// The decompositions of every variant of each character in `chars`.
+    #[wasm_bindgen]
+    pub fn decompose(chars: String) -> JsValue {
+        let decompositions: Vec<Decomposition> = with_table(|table| crate::decompose(table, &chars).into_iter()
+            .map(|m| Decomposition {
+                character: m.character.to_string(),
+                tag: table.tag_for_display(m.character, &m.tag),
+                ids: m.ids.to_string(),
+            })
+            .collect());
+        serde_wasm_bindgen::to_value(&decompositions).unwrap()
     }
 
     #[wasm_bindgen]
     pub fn find(needles_str: String) -> JsValue {
-        let table = get_table();
         let needle_strs: Vec<String> = needles_str
             .split_whitespace()
             .map(|s| s.to_string())
             .collect();
 
-        let mut result = match crate::search_find(&table, &needle_strs) {
-            Ok(tchars) => tchars.iter().map(|(c, _t)| format!("{}", c)).collect(),
-            Err(e) => vec![format!("Error: {}", e)],
-        };
-        result.dedup();
-
-        serde_wasm_bindgen::to_value(&SearchResult { results: result }).unwrap()
+        to_js(with_table(|table| crate::search_find(table, &needle_strs)).into())
     }
 
+    /// Like `find`, but a character needs to contain only one of the needles.
     #[wasm_bindgen]
-    pub fn match_pattern(pattern: String) -> JsValue {
-        let table = get_table();
+    pub fn find_any(needles_str: String) -> JsValue {
+        let needle_strs: Vec<String> = needles_str
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+
+        to_js(with_table(|table| crate::search_find_any(table, &needle_strs)).into())
+    }
 
-        let mut result = match crate::search_match(&table, &pattern) {
-            Ok(tchars) => tchars.iter().map(|(c, _t)| format!("{}", c)).collect(),
-            Err(e) => vec![format!("Error: {}", e)],
+    /// Narrows `prev`, the characters `find` returned for `prev_needles`,
+    /// by one more needle.
+    #[wasm_bindgen]
+    pub fn refine_find(prev_needles: String, prev: JsValue, needle: String) -> JsValue {
+        let prev: Vec<String> = match serde_wasm_bindgen::from_value(prev) {
+            Ok(prev) => prev,
+            Err(e) => return to_js(Err(e.to_string()).into()),
         };
-        result.dedup();
+        let chars: Vec<char> = prev.iter().filter_map(|s| s.chars().next()).collect();
+        let needle_strs: Vec<String> = prev_needles
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+        to_js(with_table(|table| crate::search_find_refine(table, &chars, &needle_strs, &needle)).into())
+    }
+
+    /// One page of a `find` result, with the number of results in all.
+    #[derive(Serialize)]
+    pub struct Page {
+        pub ok: bool,
+        pub error: Option<String>,
+        pub total: usize,
+        pub results: Vec<String>,
+    }
+
+    thread_local! {
+        // The needles of the last `find_page` and all of its results, so
+        // that turning pages does not search again
+        static LAST_FIND: RefCell<Option<(String, Vec<String>)>> = const { RefCell::new(None) };
+    }
+
+    /// Up to `limit` characters of the result of `find`, starting at
+    /// `offset`. The whole result of the last needles is kept, so asking
+    /// for another page of them only slices it.
+    #[wasm_bindgen]
+    pub fn find_page(needles_str: String, offset: usize, limit: usize) -> JsValue {
+        let page = LAST_FIND.with_borrow_mut(|last| {
+            let results = match last {
+                Some((needles, results)) if *needles == needles_str => results,
+                _ => {
+                    let needle_strs: Vec<String> = needles_str
+                        .split_whitespace()
+                        .map(|s| s.to_string())
+                        .collect();
+                    match SearchResult::from(with_table(|table| crate::search_find(table, &needle_strs))) {
+                        SearchResult { ok: true, results, .. } => &mut last.insert((needles_str, results)).1,
+                        SearchResult { error, .. } => return Page { ok: false, error, total: 0, results: vec![] },
+                    }
+                }
+            };
+            Page {
+                ok: true,
+                error: None,
+                total: results.len(),
+                results: results.iter().skip(offset).take(limit).cloned().collect(),
+            }
+        });
+        serde_wasm_bindgen::to_value(&page).unwrap()
+    }
 
-        serde_wasm_bindgen::to_value(&SearchResult { results: result }).unwrap()
+    #[wasm_bindgen]
+    pub fn match_pattern(pattern: String) -> JsValue {
+        to_js(with_table(|table| crate::search_match(table, &pattern)).into())
     }
 
     #[wasm_bindgen]
     pub fn pmatch(pattern: String) -> JsValue {
-        let table = get_table();
+        to_js(with_table(|table| crate::search_pmatch(table, &pattern)).into())
+    }
+}
 
-        let mut result = match crate::search_pmatch(&table, &pattern) {
-            Ok(tchars) => tchars.iter().map(|(c, _t)| format!("{}", c)).collect(),
-            Err(e) => vec![format!("Error: {}", e)],
-        };
-        result.dedup();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn table(data: &str) -> IDSTable {
+        IDSTable::load_from_string(data).unwrap()
+    }
+
+    fn needles(strs: &[&str]) -> Vec<String> {
+        strs.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn chars(result: &[(char, Tag)]) -> Vec<char> {
+        result.iter().map(|(c, _)| *c).collect()
+    }
+
+    #[test]
+    fn find_includes_self_by_default() {
+        let t = table("U+53E3 口 口\nU+53F6 叶 ⿰口十\nU+5403 吃 ⿰口乞");
+        let result = chars(&search_find(&t, &needles(&["口"])).unwrap());
+        assert_eq!(result, vec!['口', '叶', '吃']);
+    }
+
+    #[test]
+    fn find_can_exclude_self() {
+        let t = table("U+53E3 口 口\nU+53F6 叶 ⿰口十\nU+5403 吃 ⿰口乞");
+        let options = FindOptions { include_self: false, ..FindOptions::default() };
+        let result = chars(&search_find_with(&t, &needles(&["口"]), &options).unwrap());
+        assert_eq!(result, vec!['叶', '吃']);
+        // a needle spelled out as the character's own IDS is also "self"
+        let result = chars(&search_find_with(&t, &needles(&["⿰口十"]), &options).unwrap());
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn match_any_unions_patterns() {
+        let t = table("U+6E05 清 ⿰氵青\nU+83C1 菁 ⿱艹青\nU+6CB3 河 ⿰氵可");
+        let result = chars(&search_match_any(&t, &needles(&["⿰氵青", "⿱艹青", "⿰氵."])).unwrap());
+        assert_eq!(result, vec!['河', '清', '菁']);
+        assert!(search_match_any(&t, &needles(&["⿰氵青青"])).is_err());
+    }
+
+    #[test]
+    fn detailed_results_carry_ids() {
+        let t = table("U+9752 青 ⿱龶⺝[G] ⿱龶月[T]\nU+6E05 清 ⿰氵青");
+        let result = search_match_detailed(&t, "⿰氵青").unwrap();
+        assert_eq!(result, vec![SearchMatch {
+            character: '清',
+            tag: Tag::Variant(vec![]),
+            ids: parse("⿰氵青").unwrap(),
+        }]);
+        let result = search_find_detailed(&t, &needles(&["月"])).unwrap();
+        let ids: Vec<String> = result.iter().map(|m| m.ids.to_string()).collect();
+        assert_eq!(ids, vec!["⿰氵青", "⿱龶月"]);
+    }
+
+    #[test]
+    fn sort_results_by_key() {
+        let mut t = table("U+53E3 口 口\nU+53F6 叶 ⿰口十\nU+5668 器 ⿳吅犬吅\nU+5417 吗 ⿰口⿹马一");
+        t.load_strokes_from_string("口 3\n叶 5\n器 16\n");
+        t.load_frequencies_from_string("吗 900\n叶 300\n");
+        let mut result = t.search_find_within(&['器', '吗', '叶', '口'], &[]);
+
+        sort_results(&t, &mut result, SortKey::Strokes);
+        assert_eq!(chars(&result), vec!['口', '叶', '器', '吗']);
+        sort_results(&t, &mut result, SortKey::Frequency);
+        assert_eq!(chars(&result), vec!['吗', '叶', '口', '器']);
+        sort_results(&t, &mut result, SortKey::Depth);
+        assert_eq!(chars(&result), vec!['口', '叶', '器', '吗']);
+        sort_results(&t, &mut result, SortKey::Codepoint);
+        assert_eq!(chars(&result), vec!['口', '叶', '吗', '器']);
+        assert!("strokes".parse::<SortKey>().is_ok());
+        assert!("radical".parse::<SortKey>().is_err());
+    }
+
+    #[test]
+    fn normalize_matches_compatibility_ideographs() {
+        let mut t = table("U+8C48 豈 ⿱山豆\nU+6137 愷 ⿰忄豈\n");
+        assert!(search_find(&t, &needles(&["\u{F900}"])).unwrap().is_empty());
+        t.set_normalize(true);
+        assert_eq!(chars(&search_find(&t, &needles(&["\u{F900}"])).unwrap()), vec!['愷']);
+
+        let mut t = table("U+F900 \u{F900} ⿱山豆\n");
+        t.set_normalize(true);
+        assert_eq!(t.primary_decomposition('豈'), Some(&parse("⿱山豆").unwrap()));
+    }
+
+    #[test]
+    fn find_single_component_uses_index() {
+        let data = "U+53E3 口 口\nU+5341 十 十\nU+53E4 古 ⿱十口\nU+8A00 言 言\nU+8A41 詁 ⿰言古\nU+8A41 詁 ⿰言古[T]\nU+56FA 固 ⿴口古\n";
+        let mut t = table(data);
+        for c in ['口', '十', '古', '言', '木'] {
+            let generic: Vec<(char, Tag)> = t.iter()
+                .filter(|(_, ids)| t.ids_has_subcomponent(ids, &IDS::Char(c)))
+                .map(|((k, tag), _)| (k, tag.clone()))
+                .collect::<alloc::collections::BTreeSet<_>>().into_iter().collect();
+            assert_eq!(t.find_leaf_only(c), generic, "{}", c);
+        }
+        assert_eq!(chars(&search_find(&t, &needles(&["十"])).unwrap()), vec!['十', '古', '固', '詁', '詁']);
+
+        t.retain_reachable(&['古']);
+        assert_eq!(chars(&t.find_leaf_only('口')), vec!['口', '古']);
+    }
+
+    #[test]
+    fn find_skips_index_for_exact_modifiers() {
+        let mut t = table("U+6B62 止 止\nU+6B65 步 ⿱止↔少\nU+6B66 武 ⿰止戈");
+        t.set_exact_modifiers(true);
+        let generic: Vec<(char, Tag)> = t.iter()
+            .filter(|(_, ids)| t.ids_has_subcomponent(ids, &IDS::Char('止')))
+            .map(|((k, tag), _)| (k, tag.clone()))
+            .collect::<alloc::collections::BTreeSet<_>>().into_iter().collect();
+        assert_eq!(search_find(&t, &needles(&["止"])).unwrap(), generic);
+        assert_eq!(chars(&generic), vec!['止', '武']);
+        assert_eq!(search_query(&t, "止").unwrap(), vec!['止', '武']);
+        t.set_exact_modifiers(false);
+        assert_eq!(chars(&search_find(&t, &needles(&["止"])).unwrap()), vec!['止', '步', '武']);
+    }
+
+    #[test]
+    fn find_skips_index_for_preferred_kind() {
+        let mut t = table("U+9752 青 ⿱龶月[GS] ⿱土円[GA]\nU+6E05 清 ⿰氵青\nU+5186 円 円\nU+6C35 氵 氵");
+        assert_eq!(chars(&search_find(&t, &needles(&["円"])).unwrap()), vec!['円', '清', '青']);
+        t.set_preferred_kind(Some(ids::DecompositionKind::Structural));
+        // 青 still lists its own apparent decomposition, but 清 expands 青 structurally
+        assert_eq!(chars(&search_find(&t, &needles(&["円"])).unwrap()), vec!['円', '青']);
+        assert!(search_find(&t, &needles(&["円", "氵"])).unwrap().is_empty());
+        assert_eq!(search_query(&t, "円").unwrap(), vec!['円', '青']);
+    }
+
+    #[test]
+    fn find_special_matches_placeholder() {
+        let t = table("U+6E45 湅 ⿰氵柬\nU+2B81D 𫠝 ⿰{柬中}攵\nU+2B81E 𫠞 ⿱艹𫠝\nU+2B81F 𫠟 ⿰{柬}攵\n");
+        assert_eq!(chars(&search_find_special(&t, "柬中").unwrap()), vec!['𫠝', '𫠞']);
+        assert_eq!(chars(&search_find_special(&t, "{柬}").unwrap()), vec!['𫠟']);
+        assert!(search_find_special(&t, "{}").is_err());
+    }
+
+    #[test]
+    fn find_any_takes_union() {
+        let t = table("U+65E5 日 日\nU+6708 月 月\nU+660E 明 ⿰日月\nU+6714 朔 ⿰屰月\nU+65E6 旦 ⿱日一\nU+53E3 口 口\n");
+        assert_eq!(chars(&search_find(&t, &needles(&["日", "月"])).unwrap()), vec!['明']);
+        assert_eq!(chars(&search_find_any(&t, &needles(&["日", "月"])).unwrap()), vec!['日', '旦', '明', '月', '朔']);
+        assert!(search_find_any(&t, &needles(&["⿰日"])).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn timeout_returns_partial_result() {
+        let t = table("U+65E5 日 日\nU+6708 月 月\nU+660E 明 ⿰日月\nU+6714 朔 ⿰屰月\n");
+        let budget = std::time::Duration::from_secs(60);
+        let search = search_find_with_timeout(&t, &needles(&["月"]), &FindOptions::default(), budget).unwrap();
+        assert!(!search.timed_out);
+        assert_eq!(search.results, search_find(&t, &needles(&["月"])).unwrap());
+        let search = search_match_with_timeout(&t, "⿰.月", budget).unwrap();
+        assert_eq!(chars(&search.results), vec!['明', '朔']);
+
+        let search = search_match_with_timeout(&t, "⿰.月", std::time::Duration::ZERO).unwrap();
+        assert!(search.timed_out);
+        assert!(search.results.is_empty());
+        assert!(search_match_with_timeout(&t, "⿰.月月", budget).is_err());
+    }
+
+    #[test]
+    fn match_metered_counts_work() {
+        let t = table("U+6C35 氵 氵\nU+9752 青 青\nU+6E05 清 ⿰氵青\nU+6F8C 澌 ⿰氵斯\nU+6F84 澄 ⿰氵登\n");
+        let (result, metrics) = search_match_metered(&t, "⿰氵青").unwrap();
+        assert_eq!(result, search_match(&t, "⿰氵青").unwrap());
+        assert_eq!(metrics.candidates_scanned, 5);
+        assert_eq!(metrics.expansions, 0);
+        assert!(metrics.nodes_visited > metrics.candidates_scanned);
+
+        let t = table("U+6C35 氵 氵\nU+9752 青 青\nU+6E05 清 ⿰氵青\nU+6F3F 漿 ⿱清清\n");
+        let (_, metrics) = search_match_metered(&t, "⿱⿰氵.⿰氵.").unwrap();
+        assert_eq!(metrics.expansions, 2);
+    }
+
+    #[test]
+    fn unique_chars_keeps_first_tag() {
+        let t = table("U+53E3 口 口\nU+5341 十 十\nU+53E4 古 ⿱十口[G]\nU+53E4 古 ⿱十口[T]\nU+53E4 古 ⿱十口\nU+53E4 古 ⿱十口\n");
+        let mut result = search_find(&t, &needles(&["十"])).unwrap();
+        assert_eq!(chars(&result), vec!['十', '古', '古', '古', '古']);
+        unique_chars(&mut result);
+        assert_eq!(chars(&result), vec!['十', '古']);
+        let mut pmatched = search_pmatch(&t, "⿱十.").unwrap();
+        assert_eq!(chars(&pmatched), vec!['古', '古', '古', '古']);
+        unique_chars(&mut pmatched);
+        assert_eq!(chars(&pmatched), vec!['古']);
+        let first_tag = t.iter().filter(|((k, _), _)| *k == '古').map(|((_, tag), _)| tag.clone()).min().unwrap();
+        assert_eq!(result[1].1, first_tag);
+    }
+
+    #[test]
+    fn decompose_lists_variants() {
+        let t = table("U+6797 林 ⿰木木 ⿱木木[J]\nU+6728 木 木\n");
+        let result = decompose(&t, "林 鬱木");
+        let shown: Vec<String> = result.iter().map(|m| format!("{}{}{}", m.character, m.ids, m.tag)).collect();
+        assert_eq!(shown, vec!["林⿰木木", "林⿱木木J", "木木"]);
+    }
+
+    #[test]
+    fn simplest_first_ranks_by_leaf_count() {
+        let t = table("U+53E3 口 口\nU+5341 十 十\nU+72AC 犬 犬\nU+5668 器 ⿳⿰口口犬⿰口口\nU+53F6 叶 ⿰口十\nU+5415 吕 ⿱口口\n");
+        let mut result = search_find(&t, &needles(&["口"])).unwrap();
+        sort_results(&t, &mut result, SortKey::Simplest);
+        assert_eq!(chars(&result), vec!['口', '叶', '吕', '器']);
+    }
+
+    #[test]
+    fn sample_results_is_seeded_and_without_replacement() {
+        let results: Vec<(char, Tag)> = "一二三四五六七八九十".chars().map(|k| (k, Tag::Variant(vec![]))).collect();
+        let picked = sample_results(results.clone(), 4, 42).unwrap();
+        assert_eq!(picked, sample_results(results.clone(), 4, 42).unwrap());
+        let mut distinct = chars(&picked);
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 4);
+        assert_eq!(sample_results(results.clone(), 10, 7).unwrap().len(), 10);
+        assert!(sample_results(results, 11, 7).is_err());
+    }
+
+    #[test]
+    fn iter_searches_yield_the_same_matches() {
+        let t = table("U+53E3 口 口\nU+5415 吕 ⿱口口\nU+54C1 品 ⿱口⿰口口\nU+56DE 回 ⿴口口\n");
+        let mut streamed: Vec<(char, Tag)> = search_match_iter(&t, "⿱口.").unwrap().collect();
+        streamed.sort();
+        assert_eq!(streamed, search_match(&t, "⿱口.").unwrap());
+        let mut streamed: Vec<(char, Tag)> = search_pmatch_iter(&t, "⿰口口").unwrap().collect();
+        streamed.sort();
+        assert_eq!(streamed, search_pmatch(&t, "⿰口口").unwrap());
+        assert_eq!(search_pmatch_iter(&t, "⿱口.").unwrap().take(1).count(), 1);
+        assert!(search_match_iter(&t, "{口").is_err());
+    }
+
+    #[test]
+    fn find_composition_needle_matches_structure() {
+        let t = table("U+8279 艹 艹\nU+4EBB 亻 亻\nU+5315 匕 匕\nU+53E3 口 口\nU+5316 化 ⿰亻匕\n\
+            U+82B1 花 ⿱艹化\nU+5629 嘩 ⿰口⿱艹化\nU+9774 靴 ⿱艹⿰亻匕\nU+82B2 芲 ⿰艹化\nU+54C8 哈 ⿰口花\n");
+        let result = search_find(&t, &needles(&["⿱艹化"])).unwrap();
+        assert_eq!(chars(&result), vec!['哈', '嘩', '花', '靴']);
+    }
+
+    #[test]
+    fn match_region_suffix_pins_expansion() {
+        let t = table("U+9F36 龶 龶\nU+6708 月 月\nU+2E9D ⺝ ⺝\nU+6C35 氵 氵\nU+9752 青 ⿱龶⺝[G] ⿱龶月[J]\nU+6E05 清 ⿰氵青\n");
+        assert_eq!(chars(&search_match(&t, "⿰氵⿱龶⺝").unwrap()), vec!['清']);
+        assert!(search_match(&t, "⿰氵⿱龶⺝@J").unwrap().is_empty());
+        assert_eq!(chars(&search_match(&t, "⿰氵⿱龶月@J").unwrap()), vec!['清']);
+        let patterns = ["⿰氵⿱龶⺝@J".to_string(), "⿰氵⿱龶月@J".to_string()];
+        assert_eq!(chars(&search_match_any(&t, &patterns).unwrap()), vec!['清']);
+        assert!(search_match_any(&t, &patterns[..1]).unwrap().is_empty());
+        let (result, metrics) = search_match_metered(&t, "⿰氵⿱龶月@J").unwrap();
+        assert_eq!(chars(&result), vec!['清']);
+        assert!(metrics.expansions > 0);
+        assert!(search_match_metered(&t, "⿰氵⿱龶⺝@J").unwrap().0.is_empty());
+        assert!(search_match_any(&t, &["⿰氵@J".to_string(), "⿰{".to_string()]).unwrap_err().starts_with("Cannot parse pattern ⿰{: "));
+        assert_eq!(split_region("⿰氵."), ("⿰氵.", None));
+        assert_eq!(split_region("⿰氵.@"), ("⿰氵.@", None));
+    }
+
+    #[test]
+    fn occurrences_are_grouped_by_count() {
+        let t = table("U+53E3 口 口\nU+5405 吅 ⿰口口\nU+54C1 品 ⿱口⿰口口\nU+35CA 㗊 ⿱吅吅\nU+5415 吕 ⿱口口\nU+53F6 叶 ⿰口十\nU+5341 十 十\n");
+        let groups = search_occurrences(&t, "口").unwrap();
+        assert_eq!(groups, vec![(4, vec!['㗊']), (3, vec!['品']), (2, vec!['吅', '吕']), (1, vec!['口', '叶'])]);
+    }
+
+    #[test]
+    fn find_refine_keeps_variants_matching_the_earlier_needles() {
+        let t = table("U+6C35 氵 氵\nU+9752 青 青\nU+6708 月 月\nU+6E05 清 ⿰氵青\nU+E000 \u{E000} ⿰氵月[G] ⿱月青[T]\n");
+        let previous: Vec<char> = chars(&search_find(&t, &needles(&["氵"])).unwrap());
+        assert_eq!(previous, vec!['氵', '清', '\u{E000}']);
+        // the [T] variant has 青 but not 氵
+        let refined = search_find_refine(&t, &previous, &needles(&["氵"]), "青").unwrap();
+        assert_eq!(refined, search_find(&t, &needles(&["氵", "青"])).unwrap());
+        assert_eq!(chars(&refined), vec!['清']);
+        assert_eq!(search_find_refine(&t, &previous, &needles(&["氵"]), "-青").unwrap(), search_find(&t, &needles(&["氵", "-青"])).unwrap());
+        assert!(search_find_refine(&t, &previous, &needles(&["氵"]), "⿰").unwrap_err().starts_with("Cannot parse needle ⿰: "));
+    }
+
+    #[test]
+    fn find_char_level_combines_variants() {
+        let t = table("U+6C35 氵 氵\nU+9752 青 青\nU+6708 月 月\nU+E000 \u{E000} ⿰氵月[G] ⿰氵青[T]\nU+6E05 清 ⿰氵青\n");
+        assert_eq!(search_find_char_level(&t, &needles(&["青"])).unwrap(), vec!['清', '青', '\u{E000}']);
+        assert_eq!(search_find_char_level(&t, &needles(&["月", "青"])).unwrap(), vec!['\u{E000}']);
+        assert!(search_find(&t, &needles(&["月", "青"])).unwrap().is_empty());
+        assert_eq!(search_find_char_level(&t, &needles(&["氵"])).unwrap(), vec!['氵', '清', '\u{E000}']);
+    }
+
+    #[test]
+    fn find_negated_needles() {
+        let t = table("U+6C34 水 水\nU+53E3 口 口\nU+6C93 沓 ⿱水曰\nU+6DE6 淦 ⿰氵金\nU+6C35 氵 水\nU+55D2 嗒 ⿰口⿱水口\nU+6CF5 泵 ⿱石水\nU+77F3 石 ⿸丆口");
+        assert_eq!(chars(&search_find(&t, &needles(&["水", "-口"])).unwrap()), vec!['水', '氵', '沓', '淦']);
+        assert_eq!(search_find(&t, &needles(&["水", "!口"])), search_find(&t, &needles(&["水", "-口"])));
+        assert_eq!(chars(&search_find(&t, &needles(&["⿱水曰", "-口"])).unwrap()), vec!['沓']);
+        assert_eq!(chars(&search_find(&t, &needles(&["-水"])).unwrap()), vec!['口', '石']);
+        assert_eq!(search_find_char_level(&t, &needles(&["水", "-石"])).unwrap(), vec!['嗒', '水', '氵', '沓', '淦']);
+        assert_eq!(search_find_char_level(&t, &needles(&["-口"])).unwrap(), vec!['水', '氵', '沓', '淦']);
+        // a needle that parses as it is is taken literally
+        let t = table("U+002D - -\nU+2D2D X ⿰--");
+        assert_eq!(chars(&search_find(&t, &needles(&["-"])).unwrap()), vec!['-', 'X']);
+        assert!(search_find(&t, &needles(&["--"])).unwrap().is_empty());
+        assert!(search_find(&t, &needles(&["-⿰"])).is_err());
+    }
+
+    #[test]
+    fn find_metered_counts_index_candidates() {
+        let t = table("U+53E3 口 口\nU+53E4 古 ⿱十口\nU+5341 十 十\nU+6C5F 江 ⿰氵工 ⿰水工");
+        assert_eq!(t.len(), 5);
+        let scanned = |ns: &[&str]| search_find_metered(&t, &needles(ns), &FindOptions::default()).map(|(_, m)| m.candidates_scanned);
+        assert_eq!(scanned(&["口"]), Ok(2));
+        assert_eq!(scanned(&["口", "十"]), Ok(5));
+        assert_eq!(scanned(&["⿱十口"]), Ok(5));
+        assert!(scanned(&["⿱十"]).is_err());
+        let (result, _) = search_find_metered(&t, &needles(&["口"]), &FindOptions::default()).unwrap();
+        assert_eq!(result, search_find(&t, &needles(&["口"])).unwrap());
+    }
 
-        serde_wasm_bindgen::to_value(&SearchResult { results: result }).unwrap()
+    #[test]
+    fn find_exact_requires_same_component_multiset() {
+        let t = table("U+65E5 日 日\nU+6708 月 月\nU+660E 明 ⿰日月\nU+6719 朋 ⿰月月\nU+5192 冒 ⿱冃目\nU+5183 冃 ⿵冂二");
+        let result = chars(&search_find_exact(&t, &needles(&["日", "月"])).unwrap());
+        assert_eq!(result, vec!['明']);
+        // both sides are expanded down to atomic leaves
+        let result = chars(&search_find_exact(&t, &needles(&["冃", "目"])).unwrap());
+        assert_eq!(result, vec!['冒']);
+        let result = chars(&search_find_exact(&t, &needles(&["冂", "二", "目"])).unwrap());
+        assert_eq!(result, vec!['冒']);
     }
 }
 
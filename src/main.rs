@@ -1,57 +1,588 @@
+use std::{collections::HashSet, fs::File, io::{BufWriter, IsTerminal, Write}, ops::Range, path::PathBuf, time::{Instant, SystemTime, UNIX_EPOCH}};
+
+use log::info;
 use structopt::StructOpt;
-use hanzi_search::{ids::IDSTable, search_find, search_match, search_pmatch};
+use hanzi_search::{format::{Format, JsonFormatter, PlainFormatter, ResultFormatter, TsvFormatter, json_string}, ids::{IDSTable, IDS, Tag, parse, parse_partial, parse_tagged}, FindOptions, SortKey, search_find, search_find_direct, search_find_metered, search_find_special, sort_results, unique_chars, search_match, search_match_iter, search_match_any, search_match_mixed, search_pmatch_at_depth, search_pmatch_at_depth_iter, search_occurrences, search_query, sample_results, with_ids};
 
 // Embed the data file into the binary
 const CHAI_DATA: &str = include_str!("../chai.txt");
 
+// Commonly confused components used by --fuzzy-components
+const DEFAULT_EQUIVALENCES: &str = "未末\n己已巳\n土士\n戊戌戍\n日曰\n";
+
+const HISTOGRAM_WIDTH: usize = 50;
+
 #[derive(StructOpt, Debug)]
 #[structopt(name = "hanzi-search")]
 struct Opt {
+    /// Prefer decompositions of this region (e.g. G, T, J) when expanding components
+    #[structopt(long, global = true)]
+    prefer_region: Option<String>,
+    /// Treat easily confused components (e.g. 未/末, 己/已/巳) as equal
+    #[structopt(long, global = true)]
+    fuzzy_components: bool,
+    /// File of equivalence sets for --fuzzy-components, one set per line
+    #[structopt(long, global = true, parse(from_os_str))]
+    equivalences: Option<PathBuf>,
+    /// Unicode-normalize the table and queries (e.g. compatibility ideographs)
+    #[structopt(long, global = true)]
+    normalize: bool,
+    /// Only match a mirrored or rotated component (e.g. 止↔) to the same modifier
+    #[structopt(long, global = true)]
+    exact_modifiers: bool,
+    /// Let a component marked with 〾 (e.g. 〾青) also match its base
+    #[structopt(long, global = true)]
+    lenient_variation_indicators: bool,
+    /// In pmatch, let a ⿻ pattern match any part containing all its components
+    #[structopt(long, global = true)]
+    loose_overlay: bool,
+    /// Let each part of a match pattern match part of the character's part in that position
+    #[structopt(long, global = true)]
+    child_subcomponent: bool,
+    /// Fold fullwidth letters in {...} components and region tags to halfwidth
+    #[structopt(long, global = true)]
+    fold_width: bool,
+    /// Only match a character with a variation selector to the same selector
+    #[structopt(long, global = true)]
+    exact_variation_selectors: bool,
+    /// Print the decomposition of each result
+    #[structopt(long, global = true)]
+    show_ids: bool,
+    /// Highlight the matched part of each decomposition (with --show-ids)
+    #[structopt(long, global = true)]
+    color: bool,
+    /// Order of results: codepoint, strokes, depth, frequency or simplest
+    #[structopt(long, global = true, default_value = "codepoint")]
+    sort: SortKey,
+    /// Same as --sort simplest: fewest components first
+    #[structopt(long, global = true)]
+    simplest_first: bool,
+    /// Print at most this many results
+    #[structopt(long, global = true)]
+    limit: Option<usize>,
+    /// Print match and pmatch results as they are found, unsorted
+    #[structopt(long, global = true, alias = "unsorted")]
+    stream: bool,
+    /// After the results, print the number of distinct characters and entries to stderr
+    #[structopt(long, global = true)]
+    summary: bool,
+    /// Print characters as U+XXXX code points
+    #[structopt(long, global = true)]
+    codepoints: bool,
+    /// Output format of results: plain, json or tsv
+    #[structopt(long, global = true, default_value = "plain")]
+    format: Format,
+    /// List each character once, even if several of its variants match
+    #[structopt(long, global = true)]
+    unique_chars: bool,
+    /// Stroke count file for --sort strokes, one `char count` per line
+    #[structopt(long, global = true, parse(from_os_str))]
+    strokes: Option<PathBuf>,
+    /// Frequency file for --sort frequency, one `char count` per line
+    #[structopt(long, global = true, parse(from_os_str))]
+    frequency: Option<PathBuf>,
     #[structopt(subcommand)]
     cmd: Command,
 }
 
+struct Output {
+    show_ids: bool,
+    color: bool,
+    sort: SortKey,
+    unique_chars: bool,
+    codepoints: bool,
+    limit: Option<usize>,
+    stream: bool,
+    summary: bool,
+    format: Format,
+}
+
+impl Output {
+    fn formatter<'a>(&self, table: &'a IDSTable) -> Box<dyn ResultFormatter + 'a> {
+        match self.format {
+            Format::Plain => Box::new(PlainFormatter { show_ids: self.show_ids, codepoints: self.codepoints, table: Some(table) }),
+            Format::Json => Box::new(JsonFormatter),
+            Format::Tsv => Box::new(TsvFormatter),
+        }
+    }
+
+    // `highlight` returns the child-index paths of the parts to highlight
+    fn print(&self, table: &IDSTable, mut result: Vec<(char, Tag)>, highlight: impl Fn(&IDS) -> Vec<Vec<usize>>) {
+        if self.unique_chars {
+            unique_chars(&mut result);
+        }
+        sort_results(table, &mut result, self.sort);
+        if let Some(limit) = self.limit {
+            result.truncate(limit);
+        }
+        if self.colored() {
+            for (c, t) in &result {
+                self.print_colored_row(table, *c, t, &highlight);
+            }
+        } else {
+            print!("{}", self.formatter(table).format(&with_ids(table, &result)));
+        }
+        self.print_summary(result.iter().map(|(c, _)| *c));
+    }
+
+    fn colored(&self) -> bool {
+        self.color && self.show_ids && self.format == Format::Plain
+    }
+
+    // Prints results in the order they come, stopping at the limit
+    fn stream(&self, table: &IDSTable, result: impl Iterator<Item = (char, Tag)>, highlight: impl Fn(&IDS) -> Vec<Vec<usize>>) {
+        let mut seen = HashSet::new();
+        let result = result.filter(|(c, _)| !self.unique_chars || seen.insert(*c));
+        let result = result.take(self.limit.unwrap_or(usize::MAX));
+        // a JSON array can only be written as a whole
+        if self.format == Format::Json {
+            let result: Vec<(char, Tag)> = result.collect();
+            print!("{}", self.formatter(table).format(&with_ids(table, &result)));
+            self.print_summary(result.into_iter().map(|(c, _)| c));
+            return;
+        }
+        let formatter = self.formatter(table);
+        let mut printed = Vec::new();
+        for (c, t) in result {
+            if self.colored() {
+                self.print_colored_row(table, c, &t, &highlight);
+            } else {
+                print!("{}", formatter.format(&with_ids(table, &[(c, t)])));
+            }
+            printed.push(c);
+        }
+        self.print_summary(printed.into_iter());
+    }
+
+    fn print_summary(&self, printed: impl Iterator<Item = char>) {
+        if !self.summary {
+            return;
+        }
+        let mut chars = HashSet::new();
+        let mut entries = 0;
+        for c in printed {
+            chars.insert(c);
+            entries += 1;
+        }
+        eprintln!("{} characters, {} entries", chars.len(), entries);
+    }
+
+    fn print_colored_row(&self, table: &IDSTable, c: char, t: &Tag, highlight: &impl Fn(&IDS) -> Vec<Vec<usize>>) {
+        let k = if self.codepoints { format!("U+{:04X}", c as u32) } else { c.to_string() };
+        let Some(ids) = table.decomposition(c, t) else {
+            return;
+        };
+        let spans: Vec<Range<usize>> = highlight(ids).iter()
+            .filter_map(|path| ids.display_span(path))
+            .collect();
+        println!("{} {}\t{}", k, table.tag_for_display(c, t), colorize(&ids.to_string(), &spans));
+    }
+}
+
+fn colorize(text: &str, spans: &[Range<usize>]) -> String {
+    let mut out = String::new();
+    let mut highlighted = false;
+    for (i, c) in text.char_indices() {
+        let inside = spans.iter().any(|span| span.contains(&i));
+        if inside != highlighted {
+            out.push_str(if inside { "\x1b[1;31m" } else { "\x1b[0m" });
+            highlighted = inside;
+        }
+        out.push(c);
+    }
+    if highlighted {
+        out.push_str("\x1b[0m");
+    }
+    out
+}
+
+// The pattern argument if given, else the lines of `file`, else of stdin
+fn read_patterns(pattern: Option<String>, file: Option<PathBuf>) -> anyhow::Result<Vec<String>> {
+    if let Some(pattern) = pattern {
+        return Ok(vec![pattern]);
+    }
+    let content = match file {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => std::io::read_to_string(std::io::stdin())?,
+    };
+    Ok(content.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect())
+}
+
+fn tagged(ids: &IDS, tag: &Tag) -> String {
+    match tag.to_string() {
+        t if t.is_empty() => ids.to_string(),
+        t => format!("{}[{}]", ids, t),
+    }
+}
+
+// `c` as a JSON string
+fn json_char(c: char) -> String {
+    json_string(&c.to_string())
+}
+
+// Runs a search returning its results and how many table entries it
+// tested, logging both with RUST_LOG=info along with how long it took.
+fn timed<T>(command: &str, search: impl FnOnce() -> Result<(T, usize), String>) -> Result<T, String> {
+    let start = Instant::now();
+    let (result, candidates) = search()?;
+    info!("{}: scanned {} candidates in {:?}", command, candidates, start.elapsed());
+    Ok(result)
+}
+
+fn whole(_: &IDS) -> Vec<Vec<usize>> {
+    vec![vec![]]
+}
+
 #[derive(StructOpt, Debug)]
 enum Command {
+    #[structopt(setting = structopt::clap::AppSettings::AllowLeadingHyphen)]
     Find {
+        /// Components to look for; prefix one with - or ! to leave out characters containing it
         needles: Vec<String>,
+        /// List characters that are themselves a needle (default)
+        #[structopt(long)]
+        include_self: bool,
+        /// Leave out characters that are themselves a needle
+        #[structopt(long, conflicts_with = "include-self")]
+        exclude_self: bool,
+        /// List characters containing any of the needles
+        #[structopt(long)]
+        any: bool,
+        /// List characters containing all of the needles (default)
+        #[structopt(long, conflicts_with = "any")]
+        all: bool,
+        /// Only look at the top-level parts of each character's primary decomposition
+        #[structopt(long, conflicts_with = "any")]
+        direct: bool,
     },
+    /// Match whole characters against a pattern; without one, patterns are
+    /// read from --pattern-file, or else from stdin, one per line
     Match {
+        pattern: Option<String>,
+        #[structopt(long, parse(from_os_str))]
+        pattern_file: Option<PathBuf>,
+    },
+    MatchAny {
+        patterns: Vec<String>,
+    },
+    /// Match whole characters, with each part of the pattern exact, `.` for
+    /// anything, or `*X` for a part containing X, e.g. ⿰女*子
+    MatchMixed {
         pattern: String,
     },
+    /// Like match, but the pattern may match any part of a character
     Pmatch {
-        pattern: String,
+        pattern: Option<String>,
+        #[structopt(long, parse(from_os_str))]
+        pattern_file: Option<PathBuf>,
+        /// Only match this many levels below the whole character (0 is the character itself)
+        #[structopt(long)]
+        depth: Option<usize>,
+    },
+    /// List characters matching a boolean query of components, e.g. "(水 AND 青) OR 門"
+    Query {
+        expr: String,
+    },
+    /// Print `count` random characters containing the needles, or matching --pattern
+    Random {
+        needles: Vec<String>,
+        /// Sample from the characters matching this pattern instead
+        #[structopt(long, conflicts_with = "needles")]
+        pattern: Option<String>,
+        #[structopt(short = "n", long, default_value = "10")]
+        count: usize,
+        /// Seed for reproducible picks; defaults to the current time
+        #[structopt(long)]
+        seed: Option<u64>,
+    },
+    /// List characters containing a component, grouped by how often it occurs in them
+    Occurrences {
+        component: String,
+    },
+    /// List characters containing the un-encoded component `{text}`
+    FindSpecial {
+        text: String,
+    },
+    Histogram,
+    /// List characters differing from `character` in exactly one top-level component
+    MinimalPairs {
+        character: char,
+    },
+    /// List characters built like `character` with the component at `position` replaced
+    Analogs {
+        character: char,
+        /// Which top-level component may differ, counting from 0
+        #[structopt(long, default_value = "0")]
+        position: usize,
+    },
+    /// List every un-encoded {...} component with its number of uses, the most used first
+    Specials,
+    /// List components without an entry of their own, the most used first
+    Orphans,
+    /// Print a summary of the loaded table
+    Info,
+    /// Check a data file and report structural problems
+    Validate {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+    /// Check a single decomposition of a character against the loaded table
+    Check {
+        character: char,
+        /// The decomposition, optionally tagged as in data files (e.g. ⿰氵青[G])
+        ids: String,
+    },
+    /// Report characters added, removed or redecomposed between two data files
+    Diff {
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+        #[structopt(parse(from_os_str))]
+        new: PathBuf,
+    },
+    /// Write a JSON object mapping each atomic component to the characters containing it
+    ExportIndex {
+        #[structopt(parse(from_os_str))]
+        out: PathBuf,
     },
 }
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
     let opt = Opt::from_args();
-    let table = IDSTable::load_from_string(CHAI_DATA)?;
+    let mut table = IDSTable::load_from_string(CHAI_DATA)?;
+    table.set_preferred_region(opt.prefer_region);
+    match &opt.equivalences {
+        Some(path) => table.load_equivalences_file(path)?,
+        None => table.load_equivalences_from_string(DEFAULT_EQUIVALENCES),
+    }
+    table.set_fuzzy_components(opt.fuzzy_components);
+    table.set_normalize(opt.normalize);
+    table.set_fold_width(opt.fold_width);
+    table.set_child_subcomponent(opt.child_subcomponent);
+    table.set_exact_variation_selectors(opt.exact_variation_selectors);
+    table.set_exact_modifiers(opt.exact_modifiers);
+    table.set_lenient_variation_indicators(opt.lenient_variation_indicators);
+    table.set_loose_overlay(opt.loose_overlay);
+    if let Some(path) = &opt.strokes {
+        table.load_strokes_file(path)?;
+    }
+    if let Some(path) = &opt.frequency {
+        table.load_frequencies_file(path)?;
+    }
+    let output = Output {
+        show_ids: opt.show_ids,
+        color: opt.color && std::io::stdout().is_terminal(),
+        sort: if opt.simplest_first { SortKey::Simplest } else { opt.sort },
+        unique_chars: opt.unique_chars,
+        codepoints: opt.codepoints,
+        limit: opt.limit,
+        stream: opt.stream,
+        summary: opt.summary,
+        format: opt.format,
+    };
 
     match opt.cmd {
-        Command::Find { needles } => {
-            let result = search_find(&table, &needles)
+        Command::Find { needles, include_self, exclude_self, any, all, direct } => {
+            let options = FindOptions { include_self: include_self || !exclude_self, any: any && !all };
+            let result = timed("find", || if direct {
+                Ok((search_find_direct(&table, &needles)?, table.len()))
+            } else {
+                search_find_metered(&table, &needles, &options).map(|(result, metrics)| (result, metrics.candidates_scanned))
+            }).map_err(|e| anyhow::anyhow!(e))?;
+            let parsed: Vec<IDS> = needles.iter().filter_map(|n| parse(n).ok()).collect();
+            output.print(&table, result, |ids| {
+                parsed.iter().filter_map(|n| table.subcomponent_path(ids, n)).collect()
+            });
+        }
+
+        Command::Match { pattern, pattern_file } => {
+            for pattern in read_patterns(pattern, pattern_file)? {
+                if output.stream {
+                    let result = search_match_iter(&table, &pattern)
+                        .map_err(|e| anyhow::anyhow!(e))?;
+                    // the scan runs as results are printed, so it is not timed
+                    output.stream(&table, result, whole);
+                    continue;
+                }
+                let result = timed("match", || Ok((search_match(&table, &pattern)?, table.len())))
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                output.print(&table, result, whole);
+            }
+        }
+
+        Command::MatchAny { patterns } => {
+            let result = timed("match-any", || Ok((search_match_any(&table, &patterns)?, table.len())))
                 .map_err(|e| anyhow::anyhow!(e))?;
-            for (k, t) in result {
-                println!("{} {}", k, t);
+            output.print(&table, result, whole);
+        }
+
+        Command::MatchMixed { pattern } => {
+            let result = timed("match-mixed", || Ok((search_match_mixed(&table, &pattern)?, table.len())))
+                .map_err(|e| anyhow::anyhow!(e))?;
+            output.print(&table, result, whole);
+        }
+
+        Command::Pmatch { pattern, pattern_file, depth } => {
+            for pattern in read_patterns(pattern, pattern_file)? {
+                let parsed = parse_partial(&pattern).ok();
+                let highlight = |ids: &IDS| {
+                    parsed.iter().filter_map(|p| table.matching_subcomponent_path_at_depth(ids, p, '.', depth)).collect()
+                };
+                if output.stream {
+                    let result = search_pmatch_at_depth_iter(&table, &pattern, depth)
+                        .map_err(|e| anyhow::anyhow!(e))?;
+                    output.stream(&table, result, highlight);
+                    continue;
+                }
+                let result = timed("pmatch", || Ok((search_pmatch_at_depth(&table, &pattern, depth)?, table.len())))
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                output.print(&table, result, highlight);
             }
         }
 
-        Command::Match { pattern } => {
-            let result = search_match(&table, &pattern)
+        Command::Random { needles, pattern, count, seed } => {
+            let mut result = match pattern {
+                Some(pattern) => search_match(&table, &pattern),
+                None => search_find(&table, &needles),
+            }.map_err(|e| anyhow::anyhow!(e))?;
+            unique_chars(&mut result);
+            let seed = seed.unwrap_or_else(|| {
+                SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64)
+            });
+            let result = sample_results(result, count, seed).map_err(|e| anyhow::anyhow!(e))?;
+            output.print(&table, result, whole);
+        }
+
+        Command::Occurrences { component } => {
+            let groups = search_occurrences(&table, &component)
                 .map_err(|e| anyhow::anyhow!(e))?;
-            for (k, t) in result {
-                println!("{} {}", k, t);
+            for (count, chars) in groups {
+                println!("{}:", count);
+                for k in chars {
+                    println!("  {}", k);
+                }
             }
         }
 
-        Command::Pmatch { pattern } => {
-            let result = search_pmatch(&table, &pattern)
+        Command::FindSpecial { text } => {
+            let result = timed("find-special", || Ok((search_find_special(&table, &text)?, table.len())))
                 .map_err(|e| anyhow::anyhow!(e))?;
-            for (k, t) in result {
-                println!("{} {}", k, t);
+            output.print(&table, result, whole);
+        }
+
+        Command::MinimalPairs { character } => {
+            let result = table.minimal_pairs(character).into_iter()
+                .filter_map(|k| Some((k, table.primary_tag(k)?.clone())))
+                .collect();
+            output.print(&table, result, |_| vec![]);
+        }
+
+        Command::Analogs { character, position } => {
+            let result = table.structural_analogs(character, position).into_iter()
+                .filter_map(|k| Some((k, table.primary_tag(k)?.clone())))
+                .collect();
+            output.print(&table, result, |_| vec![]);
+        }
+
+        Command::Specials => {
+            for (special, count) in table.specials() {
+                println!("{{{}}}\t{}", special, count);
+            }
+        }
+
+        Command::Histogram => {
+            let histogram = table.glyph_count_histogram();
+            let mut rows: Vec<_> = histogram.into_iter().collect();
+            rows.sort();
+            let max = rows.iter().map(|(_, n)| *n).max().unwrap_or(0);
+            for (arity, n) in rows {
+                let width = (n * HISTOGRAM_WIDTH).div_ceil(max.max(1));
+                println!("{} {:>7} {}", arity, n, "#".repeat(width));
+            }
+        }
+
+        Command::Query { expr } => {
+            let chars = search_query(&table, &expr).map_err(|e| anyhow::anyhow!(e))?;
+            let result = chars.into_iter()
+                .filter_map(|k| Some((k, table.primary_tag(k)?.clone())))
+                .collect();
+            output.print(&table, result, |_| vec![]);
+        }
+
+        Command::Orphans => {
+            for c in table.orphan_components() {
+                println!("{}", c);
+            }
+        }
+
+        Command::ExportIndex { out } => {
+            let index = table.export_index();
+            let mut components: Vec<&char> = index.keys().collect();
+            components.sort();
+            let mut writer = BufWriter::new(File::create(&out)?);
+            write!(writer, "{{")?;
+            for (i, c) in components.into_iter().enumerate() {
+                let chars: Vec<String> = index[c].iter().map(|k| json_char(*k)).collect();
+                let separator = if i == 0 { "" } else { "," };
+                write!(writer, "{}\n{}:[{}]", separator, json_char(*c), chars.join(","))?;
+            }
+            writeln!(writer, "\n}}")?;
+            writer.flush()?;
+        }
+
+        Command::Info => {
+            let stats = table.stats();
+            println!("characters:                 {}", stats.distinct_chars);
+            println!("entries:                    {}", stats.total_entries);
+            println!("atomic characters:          {}", stats.atomic_chars);
+            println!("maximum depth:              {}", stats.max_depth);
+            println!("with multiple variants:     {}", stats.characters_with_multiple_variants);
+            table.shrink_to_fit();
+            println!("approximate memory:         {} KiB", table.approximate_memory_bytes() / 1024);
+        }
+
+        Command::Validate { file } => {
+            let (validated, issues) = IDSTable::validate_file(&file)?;
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            let entries = validated.iter().count();
+            if !issues.is_empty() {
+                anyhow::bail!("{} problems found in {} entries", issues.len(), entries);
+            }
+            println!("No problems found in {} entries", entries);
+        }
+
+        Command::Check { character, ids } => {
+            let tagged = parse_tagged(&ids).map_err(|e| anyhow::anyhow!("Cannot parse {}: {}", ids, e))?;
+            let issues = table.validate_ids(character, &tagged.tag, &tagged.ids);
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            if !issues.is_empty() {
+                anyhow::bail!("{} problems found", issues.len());
+            }
+            println!("No problems found");
+        }
+
+        Command::Diff { old, new } => {
+            let diff = IDSTable::load_file(&old)?.diff(&IDSTable::load_file(&new)?);
+            for k in &diff.added {
+                println!("+ {}", k);
+            }
+            for k in &diff.removed {
+                println!("- {}", k);
+            }
+            for change in &diff.changed {
+                println!("~ {}", change.character);
+                for (tag, ids) in &change.removed {
+                    println!("    - {}", tagged(ids, tag));
+                }
+                for (tag, ids) in &change.added {
+                    println!("    + {}", tagged(ids, tag));
+                }
             }
+            println!("{} added, {} removed, {} changed", diff.added.len(), diff.removed.len(), diff.changed.len());
         }
     }
     Ok(())
@@ -0,0 +1,295 @@
+//! Unicode normalization of the characters that matter here.
+//!
+//! Han characters are only affected by singleton canonical decompositions
+//! (CJK compatibility ideographs map to unified ideographs), for which NFC
+//! and NFD agree, so only those are handled.
+
+use alloc::string::String;
+
+/// Characters whose canonical decomposition is a single other character,
+/// sorted by the former.
+const SINGLETONS: &[(char, char)] = &[
+    ('\u{0340}', '\u{0300}'), ('\u{0341}', '\u{0301}'), ('\u{0343}', '\u{0313}'), ('\u{0374}', '\u{02B9}'),
+    ('\u{037E}', '\u{003B}'), ('\u{0387}', '\u{00B7}'), ('\u{1FBE}', '\u{03B9}'), ('\u{1FEF}', '\u{0060}'),
+    ('\u{1FFD}', '\u{00B4}'), ('\u{2000}', '\u{2002}'), ('\u{2001}', '\u{2003}'), ('\u{2126}', '\u{03A9}'),
+    ('\u{212A}', '\u{004B}'), ('\u{2329}', '\u{3008}'), ('\u{232A}', '\u{3009}'), ('\u{F900}', '\u{8C48}'),
+    ('\u{F901}', '\u{66F4}'), ('\u{F902}', '\u{8ECA}'), ('\u{F903}', '\u{8CC8}'), ('\u{F904}', '\u{6ED1}'),
+    ('\u{F905}', '\u{4E32}'), ('\u{F906}', '\u{53E5}'), ('\u{F907}', '\u{9F9C}'), ('\u{F908}', '\u{9F9C}'),
+    ('\u{F909}', '\u{5951}'), ('\u{F90A}', '\u{91D1}'), ('\u{F90B}', '\u{5587}'), ('\u{F90C}', '\u{5948}'),
+    ('\u{F90D}', '\u{61F6}'), ('\u{F90E}', '\u{7669}'), ('\u{F90F}', '\u{7F85}'), ('\u{F910}', '\u{863F}'),
+    ('\u{F911}', '\u{87BA}'), ('\u{F912}', '\u{88F8}'), ('\u{F913}', '\u{908F}'), ('\u{F914}', '\u{6A02}'),
+    ('\u{F915}', '\u{6D1B}'), ('\u{F916}', '\u{70D9}'), ('\u{F917}', '\u{73DE}'), ('\u{F918}', '\u{843D}'),
+    ('\u{F919}', '\u{916A}'), ('\u{F91A}', '\u{99F1}'), ('\u{F91B}', '\u{4E82}'), ('\u{F91C}', '\u{5375}'),
+    ('\u{F91D}', '\u{6B04}'), ('\u{F91E}', '\u{721B}'), ('\u{F91F}', '\u{862D}'), ('\u{F920}', '\u{9E1E}'),
+    ('\u{F921}', '\u{5D50}'), ('\u{F922}', '\u{6FEB}'), ('\u{F923}', '\u{85CD}'), ('\u{F924}', '\u{8964}'),
+    ('\u{F925}', '\u{62C9}'), ('\u{F926}', '\u{81D8}'), ('\u{F927}', '\u{881F}'), ('\u{F928}', '\u{5ECA}'),
+    ('\u{F929}', '\u{6717}'), ('\u{F92A}', '\u{6D6A}'), ('\u{F92B}', '\u{72FC}'), ('\u{F92C}', '\u{90CE}'),
+    ('\u{F92D}', '\u{4F86}'), ('\u{F92E}', '\u{51B7}'), ('\u{F92F}', '\u{52DE}'), ('\u{F930}', '\u{64C4}'),
+    ('\u{F931}', '\u{6AD3}'), ('\u{F932}', '\u{7210}'), ('\u{F933}', '\u{76E7}'), ('\u{F934}', '\u{8001}'),
+    ('\u{F935}', '\u{8606}'), ('\u{F936}', '\u{865C}'), ('\u{F937}', '\u{8DEF}'), ('\u{F938}', '\u{9732}'),
+    ('\u{F939}', '\u{9B6F}'), ('\u{F93A}', '\u{9DFA}'), ('\u{F93B}', '\u{788C}'), ('\u{F93C}', '\u{797F}'),
+    ('\u{F93D}', '\u{7DA0}'), ('\u{F93E}', '\u{83C9}'), ('\u{F93F}', '\u{9304}'), ('\u{F940}', '\u{9E7F}'),
+    ('\u{F941}', '\u{8AD6}'), ('\u{F942}', '\u{58DF}'), ('\u{F943}', '\u{5F04}'), ('\u{F944}', '\u{7C60}'),
+    ('\u{F945}', '\u{807E}'), ('\u{F946}', '\u{7262}'), ('\u{F947}', '\u{78CA}'), ('\u{F948}', '\u{8CC2}'),
+    ('\u{F949}', '\u{96F7}'), ('\u{F94A}', '\u{58D8}'), ('\u{F94B}', '\u{5C62}'), ('\u{F94C}', '\u{6A13}'),
+    ('\u{F94D}', '\u{6DDA}'), ('\u{F94E}', '\u{6F0F}'), ('\u{F94F}', '\u{7D2F}'), ('\u{F950}', '\u{7E37}'),
+    ('\u{F951}', '\u{964B}'), ('\u{F952}', '\u{52D2}'), ('\u{F953}', '\u{808B}'), ('\u{F954}', '\u{51DC}'),
+    ('\u{F955}', '\u{51CC}'), ('\u{F956}', '\u{7A1C}'), ('\u{F957}', '\u{7DBE}'), ('\u{F958}', '\u{83F1}'),
+    ('\u{F959}', '\u{9675}'), ('\u{F95A}', '\u{8B80}'), ('\u{F95B}', '\u{62CF}'), ('\u{F95C}', '\u{6A02}'),
+    ('\u{F95D}', '\u{8AFE}'), ('\u{F95E}', '\u{4E39}'), ('\u{F95F}', '\u{5BE7}'), ('\u{F960}', '\u{6012}'),
+    ('\u{F961}', '\u{7387}'), ('\u{F962}', '\u{7570}'), ('\u{F963}', '\u{5317}'), ('\u{F964}', '\u{78FB}'),
+    ('\u{F965}', '\u{4FBF}'), ('\u{F966}', '\u{5FA9}'), ('\u{F967}', '\u{4E0D}'), ('\u{F968}', '\u{6CCC}'),
+    ('\u{F969}', '\u{6578}'), ('\u{F96A}', '\u{7D22}'), ('\u{F96B}', '\u{53C3}'), ('\u{F96C}', '\u{585E}'),
+    ('\u{F96D}', '\u{7701}'), ('\u{F96E}', '\u{8449}'), ('\u{F96F}', '\u{8AAA}'), ('\u{F970}', '\u{6BBA}'),
+    ('\u{F971}', '\u{8FB0}'), ('\u{F972}', '\u{6C88}'), ('\u{F973}', '\u{62FE}'), ('\u{F974}', '\u{82E5}'),
+    ('\u{F975}', '\u{63A0}'), ('\u{F976}', '\u{7565}'), ('\u{F977}', '\u{4EAE}'), ('\u{F978}', '\u{5169}'),
+    ('\u{F979}', '\u{51C9}'), ('\u{F97A}', '\u{6881}'), ('\u{F97B}', '\u{7CE7}'), ('\u{F97C}', '\u{826F}'),
+    ('\u{F97D}', '\u{8AD2}'), ('\u{F97E}', '\u{91CF}'), ('\u{F97F}', '\u{52F5}'), ('\u{F980}', '\u{5442}'),
+    ('\u{F981}', '\u{5973}'), ('\u{F982}', '\u{5EEC}'), ('\u{F983}', '\u{65C5}'), ('\u{F984}', '\u{6FFE}'),
+    ('\u{F985}', '\u{792A}'), ('\u{F986}', '\u{95AD}'), ('\u{F987}', '\u{9A6A}'), ('\u{F988}', '\u{9E97}'),
+    ('\u{F989}', '\u{9ECE}'), ('\u{F98A}', '\u{529B}'), ('\u{F98B}', '\u{66C6}'), ('\u{F98C}', '\u{6B77}'),
+    ('\u{F98D}', '\u{8F62}'), ('\u{F98E}', '\u{5E74}'), ('\u{F98F}', '\u{6190}'), ('\u{F990}', '\u{6200}'),
+    ('\u{F991}', '\u{649A}'), ('\u{F992}', '\u{6F23}'), ('\u{F993}', '\u{7149}'), ('\u{F994}', '\u{7489}'),
+    ('\u{F995}', '\u{79CA}'), ('\u{F996}', '\u{7DF4}'), ('\u{F997}', '\u{806F}'), ('\u{F998}', '\u{8F26}'),
+    ('\u{F999}', '\u{84EE}'), ('\u{F99A}', '\u{9023}'), ('\u{F99B}', '\u{934A}'), ('\u{F99C}', '\u{5217}'),
+    ('\u{F99D}', '\u{52A3}'), ('\u{F99E}', '\u{54BD}'), ('\u{F99F}', '\u{70C8}'), ('\u{F9A0}', '\u{88C2}'),
+    ('\u{F9A1}', '\u{8AAA}'), ('\u{F9A2}', '\u{5EC9}'), ('\u{F9A3}', '\u{5FF5}'), ('\u{F9A4}', '\u{637B}'),
+    ('\u{F9A5}', '\u{6BAE}'), ('\u{F9A6}', '\u{7C3E}'), ('\u{F9A7}', '\u{7375}'), ('\u{F9A8}', '\u{4EE4}'),
+    ('\u{F9A9}', '\u{56F9}'), ('\u{F9AA}', '\u{5BE7}'), ('\u{F9AB}', '\u{5DBA}'), ('\u{F9AC}', '\u{601C}'),
+    ('\u{F9AD}', '\u{73B2}'), ('\u{F9AE}', '\u{7469}'), ('\u{F9AF}', '\u{7F9A}'), ('\u{F9B0}', '\u{8046}'),
+    ('\u{F9B1}', '\u{9234}'), ('\u{F9B2}', '\u{96F6}'), ('\u{F9B3}', '\u{9748}'), ('\u{F9B4}', '\u{9818}'),
+    ('\u{F9B5}', '\u{4F8B}'), ('\u{F9B6}', '\u{79AE}'), ('\u{F9B7}', '\u{91B4}'), ('\u{F9B8}', '\u{96B8}'),
+    ('\u{F9B9}', '\u{60E1}'), ('\u{F9BA}', '\u{4E86}'), ('\u{F9BB}', '\u{50DA}'), ('\u{F9BC}', '\u{5BEE}'),
+    ('\u{F9BD}', '\u{5C3F}'), ('\u{F9BE}', '\u{6599}'), ('\u{F9BF}', '\u{6A02}'), ('\u{F9C0}', '\u{71CE}'),
+    ('\u{F9C1}', '\u{7642}'), ('\u{F9C2}', '\u{84FC}'), ('\u{F9C3}', '\u{907C}'), ('\u{F9C4}', '\u{9F8D}'),
+    ('\u{F9C5}', '\u{6688}'), ('\u{F9C6}', '\u{962E}'), ('\u{F9C7}', '\u{5289}'), ('\u{F9C8}', '\u{677B}'),
+    ('\u{F9C9}', '\u{67F3}'), ('\u{F9CA}', '\u{6D41}'), ('\u{F9CB}', '\u{6E9C}'), ('\u{F9CC}', '\u{7409}'),
+    ('\u{F9CD}', '\u{7559}'), ('\u{F9CE}', '\u{786B}'), ('\u{F9CF}', '\u{7D10}'), ('\u{F9D0}', '\u{985E}'),
+    ('\u{F9D1}', '\u{516D}'), ('\u{F9D2}', '\u{622E}'), ('\u{F9D3}', '\u{9678}'), ('\u{F9D4}', '\u{502B}'),
+    ('\u{F9D5}', '\u{5D19}'), ('\u{F9D6}', '\u{6DEA}'), ('\u{F9D7}', '\u{8F2A}'), ('\u{F9D8}', '\u{5F8B}'),
+    ('\u{F9D9}', '\u{6144}'), ('\u{F9DA}', '\u{6817}'), ('\u{F9DB}', '\u{7387}'), ('\u{F9DC}', '\u{9686}'),
+    ('\u{F9DD}', '\u{5229}'), ('\u{F9DE}', '\u{540F}'), ('\u{F9DF}', '\u{5C65}'), ('\u{F9E0}', '\u{6613}'),
+    ('\u{F9E1}', '\u{674E}'), ('\u{F9E2}', '\u{68A8}'), ('\u{F9E3}', '\u{6CE5}'), ('\u{F9E4}', '\u{7406}'),
+    ('\u{F9E5}', '\u{75E2}'), ('\u{F9E6}', '\u{7F79}'), ('\u{F9E7}', '\u{88CF}'), ('\u{F9E8}', '\u{88E1}'),
+    ('\u{F9E9}', '\u{91CC}'), ('\u{F9EA}', '\u{96E2}'), ('\u{F9EB}', '\u{533F}'), ('\u{F9EC}', '\u{6EBA}'),
+    ('\u{F9ED}', '\u{541D}'), ('\u{F9EE}', '\u{71D0}'), ('\u{F9EF}', '\u{7498}'), ('\u{F9F0}', '\u{85FA}'),
+    ('\u{F9F1}', '\u{96A3}'), ('\u{F9F2}', '\u{9C57}'), ('\u{F9F3}', '\u{9E9F}'), ('\u{F9F4}', '\u{6797}'),
+    ('\u{F9F5}', '\u{6DCB}'), ('\u{F9F6}', '\u{81E8}'), ('\u{F9F7}', '\u{7ACB}'), ('\u{F9F8}', '\u{7B20}'),
+    ('\u{F9F9}', '\u{7C92}'), ('\u{F9FA}', '\u{72C0}'), ('\u{F9FB}', '\u{7099}'), ('\u{F9FC}', '\u{8B58}'),
+    ('\u{F9FD}', '\u{4EC0}'), ('\u{F9FE}', '\u{8336}'), ('\u{F9FF}', '\u{523A}'), ('\u{FA00}', '\u{5207}'),
+    ('\u{FA01}', '\u{5EA6}'), ('\u{FA02}', '\u{62D3}'), ('\u{FA03}', '\u{7CD6}'), ('\u{FA04}', '\u{5B85}'),
+    ('\u{FA05}', '\u{6D1E}'), ('\u{FA06}', '\u{66B4}'), ('\u{FA07}', '\u{8F3B}'), ('\u{FA08}', '\u{884C}'),
+    ('\u{FA09}', '\u{964D}'), ('\u{FA0A}', '\u{898B}'), ('\u{FA0B}', '\u{5ED3}'), ('\u{FA0C}', '\u{5140}'),
+    ('\u{FA0D}', '\u{55C0}'), ('\u{FA10}', '\u{585A}'), ('\u{FA12}', '\u{6674}'), ('\u{FA15}', '\u{51DE}'),
+    ('\u{FA16}', '\u{732A}'), ('\u{FA17}', '\u{76CA}'), ('\u{FA18}', '\u{793C}'), ('\u{FA19}', '\u{795E}'),
+    ('\u{FA1A}', '\u{7965}'), ('\u{FA1B}', '\u{798F}'), ('\u{FA1C}', '\u{9756}'), ('\u{FA1D}', '\u{7CBE}'),
+    ('\u{FA1E}', '\u{7FBD}'), ('\u{FA20}', '\u{8612}'), ('\u{FA22}', '\u{8AF8}'), ('\u{FA25}', '\u{9038}'),
+    ('\u{FA26}', '\u{90FD}'), ('\u{FA2A}', '\u{98EF}'), ('\u{FA2B}', '\u{98FC}'), ('\u{FA2C}', '\u{9928}'),
+    ('\u{FA2D}', '\u{9DB4}'), ('\u{FA2E}', '\u{90DE}'), ('\u{FA2F}', '\u{96B7}'), ('\u{FA30}', '\u{4FAE}'),
+    ('\u{FA31}', '\u{50E7}'), ('\u{FA32}', '\u{514D}'), ('\u{FA33}', '\u{52C9}'), ('\u{FA34}', '\u{52E4}'),
+    ('\u{FA35}', '\u{5351}'), ('\u{FA36}', '\u{559D}'), ('\u{FA37}', '\u{5606}'), ('\u{FA38}', '\u{5668}'),
+    ('\u{FA39}', '\u{5840}'), ('\u{FA3A}', '\u{58A8}'), ('\u{FA3B}', '\u{5C64}'), ('\u{FA3C}', '\u{5C6E}'),
+    ('\u{FA3D}', '\u{6094}'), ('\u{FA3E}', '\u{6168}'), ('\u{FA3F}', '\u{618E}'), ('\u{FA40}', '\u{61F2}'),
+    ('\u{FA41}', '\u{654F}'), ('\u{FA42}', '\u{65E2}'), ('\u{FA43}', '\u{6691}'), ('\u{FA44}', '\u{6885}'),
+    ('\u{FA45}', '\u{6D77}'), ('\u{FA46}', '\u{6E1A}'), ('\u{FA47}', '\u{6F22}'), ('\u{FA48}', '\u{716E}'),
+    ('\u{FA49}', '\u{722B}'), ('\u{FA4A}', '\u{7422}'), ('\u{FA4B}', '\u{7891}'), ('\u{FA4C}', '\u{793E}'),
+    ('\u{FA4D}', '\u{7949}'), ('\u{FA4E}', '\u{7948}'), ('\u{FA4F}', '\u{7950}'), ('\u{FA50}', '\u{7956}'),
+    ('\u{FA51}', '\u{795D}'), ('\u{FA52}', '\u{798D}'), ('\u{FA53}', '\u{798E}'), ('\u{FA54}', '\u{7A40}'),
+    ('\u{FA55}', '\u{7A81}'), ('\u{FA56}', '\u{7BC0}'), ('\u{FA57}', '\u{7DF4}'), ('\u{FA58}', '\u{7E09}'),
+    ('\u{FA59}', '\u{7E41}'), ('\u{FA5A}', '\u{7F72}'), ('\u{FA5B}', '\u{8005}'), ('\u{FA5C}', '\u{81ED}'),
+    ('\u{FA5D}', '\u{8279}'), ('\u{FA5E}', '\u{8279}'), ('\u{FA5F}', '\u{8457}'), ('\u{FA60}', '\u{8910}'),
+    ('\u{FA61}', '\u{8996}'), ('\u{FA62}', '\u{8B01}'), ('\u{FA63}', '\u{8B39}'), ('\u{FA64}', '\u{8CD3}'),
+    ('\u{FA65}', '\u{8D08}'), ('\u{FA66}', '\u{8FB6}'), ('\u{FA67}', '\u{9038}'), ('\u{FA68}', '\u{96E3}'),
+    ('\u{FA69}', '\u{97FF}'), ('\u{FA6A}', '\u{983B}'), ('\u{FA6B}', '\u{6075}'), ('\u{FA6C}', '\u{242EE}'),
+    ('\u{FA6D}', '\u{8218}'), ('\u{FA70}', '\u{4E26}'), ('\u{FA71}', '\u{51B5}'), ('\u{FA72}', '\u{5168}'),
+    ('\u{FA73}', '\u{4F80}'), ('\u{FA74}', '\u{5145}'), ('\u{FA75}', '\u{5180}'), ('\u{FA76}', '\u{52C7}'),
+    ('\u{FA77}', '\u{52FA}'), ('\u{FA78}', '\u{559D}'), ('\u{FA79}', '\u{5555}'), ('\u{FA7A}', '\u{5599}'),
+    ('\u{FA7B}', '\u{55E2}'), ('\u{FA7C}', '\u{585A}'), ('\u{FA7D}', '\u{58B3}'), ('\u{FA7E}', '\u{5944}'),
+    ('\u{FA7F}', '\u{5954}'), ('\u{FA80}', '\u{5A62}'), ('\u{FA81}', '\u{5B28}'), ('\u{FA82}', '\u{5ED2}'),
+    ('\u{FA83}', '\u{5ED9}'), ('\u{FA84}', '\u{5F69}'), ('\u{FA85}', '\u{5FAD}'), ('\u{FA86}', '\u{60D8}'),
+    ('\u{FA87}', '\u{614E}'), ('\u{FA88}', '\u{6108}'), ('\u{FA89}', '\u{618E}'), ('\u{FA8A}', '\u{6160}'),
+    ('\u{FA8B}', '\u{61F2}'), ('\u{FA8C}', '\u{6234}'), ('\u{FA8D}', '\u{63C4}'), ('\u{FA8E}', '\u{641C}'),
+    ('\u{FA8F}', '\u{6452}'), ('\u{FA90}', '\u{6556}'), ('\u{FA91}', '\u{6674}'), ('\u{FA92}', '\u{6717}'),
+    ('\u{FA93}', '\u{671B}'), ('\u{FA94}', '\u{6756}'), ('\u{FA95}', '\u{6B79}'), ('\u{FA96}', '\u{6BBA}'),
+    ('\u{FA97}', '\u{6D41}'), ('\u{FA98}', '\u{6EDB}'), ('\u{FA99}', '\u{6ECB}'), ('\u{FA9A}', '\u{6F22}'),
+    ('\u{FA9B}', '\u{701E}'), ('\u{FA9C}', '\u{716E}'), ('\u{FA9D}', '\u{77A7}'), ('\u{FA9E}', '\u{7235}'),
+    ('\u{FA9F}', '\u{72AF}'), ('\u{FAA0}', '\u{732A}'), ('\u{FAA1}', '\u{7471}'), ('\u{FAA2}', '\u{7506}'),
+    ('\u{FAA3}', '\u{753B}'), ('\u{FAA4}', '\u{761D}'), ('\u{FAA5}', '\u{761F}'), ('\u{FAA6}', '\u{76CA}'),
+    ('\u{FAA7}', '\u{76DB}'), ('\u{FAA8}', '\u{76F4}'), ('\u{FAA9}', '\u{774A}'), ('\u{FAAA}', '\u{7740}'),
+    ('\u{FAAB}', '\u{78CC}'), ('\u{FAAC}', '\u{7AB1}'), ('\u{FAAD}', '\u{7BC0}'), ('\u{FAAE}', '\u{7C7B}'),
+    ('\u{FAAF}', '\u{7D5B}'), ('\u{FAB0}', '\u{7DF4}'), ('\u{FAB1}', '\u{7F3E}'), ('\u{FAB2}', '\u{8005}'),
+    ('\u{FAB3}', '\u{8352}'), ('\u{FAB4}', '\u{83EF}'), ('\u{FAB5}', '\u{8779}'), ('\u{FAB6}', '\u{8941}'),
+    ('\u{FAB7}', '\u{8986}'), ('\u{FAB8}', '\u{8996}'), ('\u{FAB9}', '\u{8ABF}'), ('\u{FABA}', '\u{8AF8}'),
+    ('\u{FABB}', '\u{8ACB}'), ('\u{FABC}', '\u{8B01}'), ('\u{FABD}', '\u{8AFE}'), ('\u{FABE}', '\u{8AED}'),
+    ('\u{FABF}', '\u{8B39}'), ('\u{FAC0}', '\u{8B8A}'), ('\u{FAC1}', '\u{8D08}'), ('\u{FAC2}', '\u{8F38}'),
+    ('\u{FAC3}', '\u{9072}'), ('\u{FAC4}', '\u{9199}'), ('\u{FAC5}', '\u{9276}'), ('\u{FAC6}', '\u{967C}'),
+    ('\u{FAC7}', '\u{96E3}'), ('\u{FAC8}', '\u{9756}'), ('\u{FAC9}', '\u{97DB}'), ('\u{FACA}', '\u{97FF}'),
+    ('\u{FACB}', '\u{980B}'), ('\u{FACC}', '\u{983B}'), ('\u{FACD}', '\u{9B12}'), ('\u{FACE}', '\u{9F9C}'),
+    ('\u{FACF}', '\u{2284A}'), ('\u{FAD0}', '\u{22844}'), ('\u{FAD1}', '\u{233D5}'), ('\u{FAD2}', '\u{3B9D}'),
+    ('\u{FAD3}', '\u{4018}'), ('\u{FAD4}', '\u{4039}'), ('\u{FAD5}', '\u{25249}'), ('\u{FAD6}', '\u{25CD0}'),
+    ('\u{FAD7}', '\u{27ED3}'), ('\u{FAD8}', '\u{9F43}'), ('\u{FAD9}', '\u{9F8E}'), ('\u{2F800}', '\u{4E3D}'),
+    ('\u{2F801}', '\u{4E38}'), ('\u{2F802}', '\u{4E41}'), ('\u{2F803}', '\u{20122}'), ('\u{2F804}', '\u{4F60}'),
+    ('\u{2F805}', '\u{4FAE}'), ('\u{2F806}', '\u{4FBB}'), ('\u{2F807}', '\u{5002}'), ('\u{2F808}', '\u{507A}'),
+    ('\u{2F809}', '\u{5099}'), ('\u{2F80A}', '\u{50E7}'), ('\u{2F80B}', '\u{50CF}'), ('\u{2F80C}', '\u{349E}'),
+    ('\u{2F80D}', '\u{2063A}'), ('\u{2F80E}', '\u{514D}'), ('\u{2F80F}', '\u{5154}'), ('\u{2F810}', '\u{5164}'),
+    ('\u{2F811}', '\u{5177}'), ('\u{2F812}', '\u{2051C}'), ('\u{2F813}', '\u{34B9}'), ('\u{2F814}', '\u{5167}'),
+    ('\u{2F815}', '\u{518D}'), ('\u{2F816}', '\u{2054B}'), ('\u{2F817}', '\u{5197}'), ('\u{2F818}', '\u{51A4}'),
+    ('\u{2F819}', '\u{4ECC}'), ('\u{2F81A}', '\u{51AC}'), ('\u{2F81B}', '\u{51B5}'), ('\u{2F81C}', '\u{291DF}'),
+    ('\u{2F81D}', '\u{51F5}'), ('\u{2F81E}', '\u{5203}'), ('\u{2F81F}', '\u{34DF}'), ('\u{2F820}', '\u{523B}'),
+    ('\u{2F821}', '\u{5246}'), ('\u{2F822}', '\u{5272}'), ('\u{2F823}', '\u{5277}'), ('\u{2F824}', '\u{3515}'),
+    ('\u{2F825}', '\u{52C7}'), ('\u{2F826}', '\u{52C9}'), ('\u{2F827}', '\u{52E4}'), ('\u{2F828}', '\u{52FA}'),
+    ('\u{2F829}', '\u{5305}'), ('\u{2F82A}', '\u{5306}'), ('\u{2F82B}', '\u{5317}'), ('\u{2F82C}', '\u{5349}'),
+    ('\u{2F82D}', '\u{5351}'), ('\u{2F82E}', '\u{535A}'), ('\u{2F82F}', '\u{5373}'), ('\u{2F830}', '\u{537D}'),
+    ('\u{2F831}', '\u{537F}'), ('\u{2F832}', '\u{537F}'), ('\u{2F833}', '\u{537F}'), ('\u{2F834}', '\u{20A2C}'),
+    ('\u{2F835}', '\u{7070}'), ('\u{2F836}', '\u{53CA}'), ('\u{2F837}', '\u{53DF}'), ('\u{2F838}', '\u{20B63}'),
+    ('\u{2F839}', '\u{53EB}'), ('\u{2F83A}', '\u{53F1}'), ('\u{2F83B}', '\u{5406}'), ('\u{2F83C}', '\u{549E}'),
+    ('\u{2F83D}', '\u{5438}'), ('\u{2F83E}', '\u{5448}'), ('\u{2F83F}', '\u{5468}'), ('\u{2F840}', '\u{54A2}'),
+    ('\u{2F841}', '\u{54F6}'), ('\u{2F842}', '\u{5510}'), ('\u{2F843}', '\u{5553}'), ('\u{2F844}', '\u{5563}'),
+    ('\u{2F845}', '\u{5584}'), ('\u{2F846}', '\u{5584}'), ('\u{2F847}', '\u{5599}'), ('\u{2F848}', '\u{55AB}'),
+    ('\u{2F849}', '\u{55B3}'), ('\u{2F84A}', '\u{55C2}'), ('\u{2F84B}', '\u{5716}'), ('\u{2F84C}', '\u{5606}'),
+    ('\u{2F84D}', '\u{5717}'), ('\u{2F84E}', '\u{5651}'), ('\u{2F84F}', '\u{5674}'), ('\u{2F850}', '\u{5207}'),
+    ('\u{2F851}', '\u{58EE}'), ('\u{2F852}', '\u{57CE}'), ('\u{2F853}', '\u{57F4}'), ('\u{2F854}', '\u{580D}'),
+    ('\u{2F855}', '\u{578B}'), ('\u{2F856}', '\u{5832}'), ('\u{2F857}', '\u{5831}'), ('\u{2F858}', '\u{58AC}'),
+    ('\u{2F859}', '\u{214E4}'), ('\u{2F85A}', '\u{58F2}'), ('\u{2F85B}', '\u{58F7}'), ('\u{2F85C}', '\u{5906}'),
+    ('\u{2F85D}', '\u{591A}'), ('\u{2F85E}', '\u{5922}'), ('\u{2F85F}', '\u{5962}'), ('\u{2F860}', '\u{216A8}'),
+    ('\u{2F861}', '\u{216EA}'), ('\u{2F862}', '\u{59EC}'), ('\u{2F863}', '\u{5A1B}'), ('\u{2F864}', '\u{5A27}'),
+    ('\u{2F865}', '\u{59D8}'), ('\u{2F866}', '\u{5A66}'), ('\u{2F867}', '\u{36EE}'), ('\u{2F868}', '\u{36FC}'),
+    ('\u{2F869}', '\u{5B08}'), ('\u{2F86A}', '\u{5B3E}'), ('\u{2F86B}', '\u{5B3E}'), ('\u{2F86C}', '\u{219C8}'),
+    ('\u{2F86D}', '\u{5BC3}'), ('\u{2F86E}', '\u{5BD8}'), ('\u{2F86F}', '\u{5BE7}'), ('\u{2F870}', '\u{5BF3}'),
+    ('\u{2F871}', '\u{21B18}'), ('\u{2F872}', '\u{5BFF}'), ('\u{2F873}', '\u{5C06}'), ('\u{2F874}', '\u{5F53}'),
+    ('\u{2F875}', '\u{5C22}'), ('\u{2F876}', '\u{3781}'), ('\u{2F877}', '\u{5C60}'), ('\u{2F878}', '\u{5C6E}'),
+    ('\u{2F879}', '\u{5CC0}'), ('\u{2F87A}', '\u{5C8D}'), ('\u{2F87B}', '\u{21DE4}'), ('\u{2F87C}', '\u{5D43}'),
+    ('\u{2F87D}', '\u{21DE6}'), ('\u{2F87E}', '\u{5D6E}'), ('\u{2F87F}', '\u{5D6B}'), ('\u{2F880}', '\u{5D7C}'),
+    ('\u{2F881}', '\u{5DE1}'), ('\u{2F882}', '\u{5DE2}'), ('\u{2F883}', '\u{382F}'), ('\u{2F884}', '\u{5DFD}'),
+    ('\u{2F885}', '\u{5E28}'), ('\u{2F886}', '\u{5E3D}'), ('\u{2F887}', '\u{5E69}'), ('\u{2F888}', '\u{3862}'),
+    ('\u{2F889}', '\u{22183}'), ('\u{2F88A}', '\u{387C}'), ('\u{2F88B}', '\u{5EB0}'), ('\u{2F88C}', '\u{5EB3}'),
+    ('\u{2F88D}', '\u{5EB6}'), ('\u{2F88E}', '\u{5ECA}'), ('\u{2F88F}', '\u{2A392}'), ('\u{2F890}', '\u{5EFE}'),
+    ('\u{2F891}', '\u{22331}'), ('\u{2F892}', '\u{22331}'), ('\u{2F893}', '\u{8201}'), ('\u{2F894}', '\u{5F22}'),
+    ('\u{2F895}', '\u{5F22}'), ('\u{2F896}', '\u{38C7}'), ('\u{2F897}', '\u{232B8}'), ('\u{2F898}', '\u{261DA}'),
+    ('\u{2F899}', '\u{5F62}'), ('\u{2F89A}', '\u{5F6B}'), ('\u{2F89B}', '\u{38E3}'), ('\u{2F89C}', '\u{5F9A}'),
+    ('\u{2F89D}', '\u{5FCD}'), ('\u{2F89E}', '\u{5FD7}'), ('\u{2F89F}', '\u{5FF9}'), ('\u{2F8A0}', '\u{6081}'),
+    ('\u{2F8A1}', '\u{393A}'), ('\u{2F8A2}', '\u{391C}'), ('\u{2F8A3}', '\u{6094}'), ('\u{2F8A4}', '\u{226D4}'),
+    ('\u{2F8A5}', '\u{60C7}'), ('\u{2F8A6}', '\u{6148}'), ('\u{2F8A7}', '\u{614C}'), ('\u{2F8A8}', '\u{614E}'),
+    ('\u{2F8A9}', '\u{614C}'), ('\u{2F8AA}', '\u{617A}'), ('\u{2F8AB}', '\u{618E}'), ('\u{2F8AC}', '\u{61B2}'),
+    ('\u{2F8AD}', '\u{61A4}'), ('\u{2F8AE}', '\u{61AF}'), ('\u{2F8AF}', '\u{61DE}'), ('\u{2F8B0}', '\u{61F2}'),
+    ('\u{2F8B1}', '\u{61F6}'), ('\u{2F8B2}', '\u{6210}'), ('\u{2F8B3}', '\u{621B}'), ('\u{2F8B4}', '\u{625D}'),
+    ('\u{2F8B5}', '\u{62B1}'), ('\u{2F8B6}', '\u{62D4}'), ('\u{2F8B7}', '\u{6350}'), ('\u{2F8B8}', '\u{22B0C}'),
+    ('\u{2F8B9}', '\u{633D}'), ('\u{2F8BA}', '\u{62FC}'), ('\u{2F8BB}', '\u{6368}'), ('\u{2F8BC}', '\u{6383}'),
+    ('\u{2F8BD}', '\u{63E4}'), ('\u{2F8BE}', '\u{22BF1}'), ('\u{2F8BF}', '\u{6422}'), ('\u{2F8C0}', '\u{63C5}'),
+    ('\u{2F8C1}', '\u{63A9}'), ('\u{2F8C2}', '\u{3A2E}'), ('\u{2F8C3}', '\u{6469}'), ('\u{2F8C4}', '\u{647E}'),
+    ('\u{2F8C5}', '\u{649D}'), ('\u{2F8C6}', '\u{6477}'), ('\u{2F8C7}', '\u{3A6C}'), ('\u{2F8C8}', '\u{654F}'),
+    ('\u{2F8C9}', '\u{656C}'), ('\u{2F8CA}', '\u{2300A}'), ('\u{2F8CB}', '\u{65E3}'), ('\u{2F8CC}', '\u{66F8}'),
+    ('\u{2F8CD}', '\u{6649}'), ('\u{2F8CE}', '\u{3B19}'), ('\u{2F8CF}', '\u{6691}'), ('\u{2F8D0}', '\u{3B08}'),
+    ('\u{2F8D1}', '\u{3AE4}'), ('\u{2F8D2}', '\u{5192}'), ('\u{2F8D3}', '\u{5195}'), ('\u{2F8D4}', '\u{6700}'),
+    ('\u{2F8D5}', '\u{669C}'), ('\u{2F8D6}', '\u{80AD}'), ('\u{2F8D7}', '\u{43D9}'), ('\u{2F8D8}', '\u{6717}'),
+    ('\u{2F8D9}', '\u{671B}'), ('\u{2F8DA}', '\u{6721}'), ('\u{2F8DB}', '\u{675E}'), ('\u{2F8DC}', '\u{6753}'),
+    ('\u{2F8DD}', '\u{233C3}'), ('\u{2F8DE}', '\u{3B49}'), ('\u{2F8DF}', '\u{67FA}'), ('\u{2F8E0}', '\u{6785}'),
+    ('\u{2F8E1}', '\u{6852}'), ('\u{2F8E2}', '\u{6885}'), ('\u{2F8E3}', '\u{2346D}'), ('\u{2F8E4}', '\u{688E}'),
+    ('\u{2F8E5}', '\u{681F}'), ('\u{2F8E6}', '\u{6914}'), ('\u{2F8E7}', '\u{3B9D}'), ('\u{2F8E8}', '\u{6942}'),
+    ('\u{2F8E9}', '\u{69A3}'), ('\u{2F8EA}', '\u{69EA}'), ('\u{2F8EB}', '\u{6AA8}'), ('\u{2F8EC}', '\u{236A3}'),
+    ('\u{2F8ED}', '\u{6ADB}'), ('\u{2F8EE}', '\u{3C18}'), ('\u{2F8EF}', '\u{6B21}'), ('\u{2F8F0}', '\u{238A7}'),
+    ('\u{2F8F1}', '\u{6B54}'), ('\u{2F8F2}', '\u{3C4E}'), ('\u{2F8F3}', '\u{6B72}'), ('\u{2F8F4}', '\u{6B9F}'),
+    ('\u{2F8F5}', '\u{6BBA}'), ('\u{2F8F6}', '\u{6BBB}'), ('\u{2F8F7}', '\u{23A8D}'), ('\u{2F8F8}', '\u{21D0B}'),
+    ('\u{2F8F9}', '\u{23AFA}'), ('\u{2F8FA}', '\u{6C4E}'), ('\u{2F8FB}', '\u{23CBC}'), ('\u{2F8FC}', '\u{6CBF}'),
+    ('\u{2F8FD}', '\u{6CCD}'), ('\u{2F8FE}', '\u{6C67}'), ('\u{2F8FF}', '\u{6D16}'), ('\u{2F900}', '\u{6D3E}'),
+    ('\u{2F901}', '\u{6D77}'), ('\u{2F902}', '\u{6D41}'), ('\u{2F903}', '\u{6D69}'), ('\u{2F904}', '\u{6D78}'),
+    ('\u{2F905}', '\u{6D85}'), ('\u{2F906}', '\u{23D1E}'), ('\u{2F907}', '\u{6D34}'), ('\u{2F908}', '\u{6E2F}'),
+    ('\u{2F909}', '\u{6E6E}'), ('\u{2F90A}', '\u{3D33}'), ('\u{2F90B}', '\u{6ECB}'), ('\u{2F90C}', '\u{6EC7}'),
+    ('\u{2F90D}', '\u{23ED1}'), ('\u{2F90E}', '\u{6DF9}'), ('\u{2F90F}', '\u{6F6E}'), ('\u{2F910}', '\u{23F5E}'),
+    ('\u{2F911}', '\u{23F8E}'), ('\u{2F912}', '\u{6FC6}'), ('\u{2F913}', '\u{7039}'), ('\u{2F914}', '\u{701E}'),
+    ('\u{2F915}', '\u{701B}'), ('\u{2F916}', '\u{3D96}'), ('\u{2F917}', '\u{704A}'), ('\u{2F918}', '\u{707D}'),
+    ('\u{2F919}', '\u{7077}'), ('\u{2F91A}', '\u{70AD}'), ('\u{2F91B}', '\u{20525}'), ('\u{2F91C}', '\u{7145}'),
+    ('\u{2F91D}', '\u{24263}'), ('\u{2F91E}', '\u{719C}'), ('\u{2F91F}', '\u{243AB}'), ('\u{2F920}', '\u{7228}'),
+    ('\u{2F921}', '\u{7235}'), ('\u{2F922}', '\u{7250}'), ('\u{2F923}', '\u{24608}'), ('\u{2F924}', '\u{7280}'),
+    ('\u{2F925}', '\u{7295}'), ('\u{2F926}', '\u{24735}'), ('\u{2F927}', '\u{24814}'), ('\u{2F928}', '\u{737A}'),
+    ('\u{2F929}', '\u{738B}'), ('\u{2F92A}', '\u{3EAC}'), ('\u{2F92B}', '\u{73A5}'), ('\u{2F92C}', '\u{3EB8}'),
+    ('\u{2F92D}', '\u{3EB8}'), ('\u{2F92E}', '\u{7447}'), ('\u{2F92F}', '\u{745C}'), ('\u{2F930}', '\u{7471}'),
+    ('\u{2F931}', '\u{7485}'), ('\u{2F932}', '\u{74CA}'), ('\u{2F933}', '\u{3F1B}'), ('\u{2F934}', '\u{7524}'),
+    ('\u{2F935}', '\u{24C36}'), ('\u{2F936}', '\u{753E}'), ('\u{2F937}', '\u{24C92}'), ('\u{2F938}', '\u{7570}'),
+    ('\u{2F939}', '\u{2219F}'), ('\u{2F93A}', '\u{7610}'), ('\u{2F93B}', '\u{24FA1}'), ('\u{2F93C}', '\u{24FB8}'),
+    ('\u{2F93D}', '\u{25044}'), ('\u{2F93E}', '\u{3FFC}'), ('\u{2F93F}', '\u{4008}'), ('\u{2F940}', '\u{76F4}'),
+    ('\u{2F941}', '\u{250F3}'), ('\u{2F942}', '\u{250F2}'), ('\u{2F943}', '\u{25119}'), ('\u{2F944}', '\u{25133}'),
+    ('\u{2F945}', '\u{771E}'), ('\u{2F946}', '\u{771F}'), ('\u{2F947}', '\u{771F}'), ('\u{2F948}', '\u{774A}'),
+    ('\u{2F949}', '\u{4039}'), ('\u{2F94A}', '\u{778B}'), ('\u{2F94B}', '\u{4046}'), ('\u{2F94C}', '\u{4096}'),
+    ('\u{2F94D}', '\u{2541D}'), ('\u{2F94E}', '\u{784E}'), ('\u{2F94F}', '\u{788C}'), ('\u{2F950}', '\u{78CC}'),
+    ('\u{2F951}', '\u{40E3}'), ('\u{2F952}', '\u{25626}'), ('\u{2F953}', '\u{7956}'), ('\u{2F954}', '\u{2569A}'),
+    ('\u{2F955}', '\u{256C5}'), ('\u{2F956}', '\u{798F}'), ('\u{2F957}', '\u{79EB}'), ('\u{2F958}', '\u{412F}'),
+    ('\u{2F959}', '\u{7A40}'), ('\u{2F95A}', '\u{7A4A}'), ('\u{2F95B}', '\u{7A4F}'), ('\u{2F95C}', '\u{2597C}'),
+    ('\u{2F95D}', '\u{25AA7}'), ('\u{2F95E}', '\u{25AA7}'), ('\u{2F95F}', '\u{7AEE}'), ('\u{2F960}', '\u{4202}'),
+    ('\u{2F961}', '\u{25BAB}'), ('\u{2F962}', '\u{7BC6}'), ('\u{2F963}', '\u{7BC9}'), ('\u{2F964}', '\u{4227}'),
+    ('\u{2F965}', '\u{25C80}'), ('\u{2F966}', '\u{7CD2}'), ('\u{2F967}', '\u{42A0}'), ('\u{2F968}', '\u{7CE8}'),
+    ('\u{2F969}', '\u{7CE3}'), ('\u{2F96A}', '\u{7D00}'), ('\u{2F96B}', '\u{25F86}'), ('\u{2F96C}', '\u{7D63}'),
+    ('\u{2F96D}', '\u{4301}'), ('\u{2F96E}', '\u{7DC7}'), ('\u{2F96F}', '\u{7E02}'), ('\u{2F970}', '\u{7E45}'),
+    ('\u{2F971}', '\u{4334}'), ('\u{2F972}', '\u{26228}'), ('\u{2F973}', '\u{26247}'), ('\u{2F974}', '\u{4359}'),
+    ('\u{2F975}', '\u{262D9}'), ('\u{2F976}', '\u{7F7A}'), ('\u{2F977}', '\u{2633E}'), ('\u{2F978}', '\u{7F95}'),
+    ('\u{2F979}', '\u{7FFA}'), ('\u{2F97A}', '\u{8005}'), ('\u{2F97B}', '\u{264DA}'), ('\u{2F97C}', '\u{26523}'),
+    ('\u{2F97D}', '\u{8060}'), ('\u{2F97E}', '\u{265A8}'), ('\u{2F97F}', '\u{8070}'), ('\u{2F980}', '\u{2335F}'),
+    ('\u{2F981}', '\u{43D5}'), ('\u{2F982}', '\u{80B2}'), ('\u{2F983}', '\u{8103}'), ('\u{2F984}', '\u{440B}'),
+    ('\u{2F985}', '\u{813E}'), ('\u{2F986}', '\u{5AB5}'), ('\u{2F987}', '\u{267A7}'), ('\u{2F988}', '\u{267B5}'),
+    ('\u{2F989}', '\u{23393}'), ('\u{2F98A}', '\u{2339C}'), ('\u{2F98B}', '\u{8201}'), ('\u{2F98C}', '\u{8204}'),
+    ('\u{2F98D}', '\u{8F9E}'), ('\u{2F98E}', '\u{446B}'), ('\u{2F98F}', '\u{8291}'), ('\u{2F990}', '\u{828B}'),
+    ('\u{2F991}', '\u{829D}'), ('\u{2F992}', '\u{52B3}'), ('\u{2F993}', '\u{82B1}'), ('\u{2F994}', '\u{82B3}'),
+    ('\u{2F995}', '\u{82BD}'), ('\u{2F996}', '\u{82E6}'), ('\u{2F997}', '\u{26B3C}'), ('\u{2F998}', '\u{82E5}'),
+    ('\u{2F999}', '\u{831D}'), ('\u{2F99A}', '\u{8363}'), ('\u{2F99B}', '\u{83AD}'), ('\u{2F99C}', '\u{8323}'),
+    ('\u{2F99D}', '\u{83BD}'), ('\u{2F99E}', '\u{83E7}'), ('\u{2F99F}', '\u{8457}'), ('\u{2F9A0}', '\u{8353}'),
+    ('\u{2F9A1}', '\u{83CA}'), ('\u{2F9A2}', '\u{83CC}'), ('\u{2F9A3}', '\u{83DC}'), ('\u{2F9A4}', '\u{26C36}'),
+    ('\u{2F9A5}', '\u{26D6B}'), ('\u{2F9A6}', '\u{26CD5}'), ('\u{2F9A7}', '\u{452B}'), ('\u{2F9A8}', '\u{84F1}'),
+    ('\u{2F9A9}', '\u{84F3}'), ('\u{2F9AA}', '\u{8516}'), ('\u{2F9AB}', '\u{273CA}'), ('\u{2F9AC}', '\u{8564}'),
+    ('\u{2F9AD}', '\u{26F2C}'), ('\u{2F9AE}', '\u{455D}'), ('\u{2F9AF}', '\u{4561}'), ('\u{2F9B0}', '\u{26FB1}'),
+    ('\u{2F9B1}', '\u{270D2}'), ('\u{2F9B2}', '\u{456B}'), ('\u{2F9B3}', '\u{8650}'), ('\u{2F9B4}', '\u{865C}'),
+    ('\u{2F9B5}', '\u{8667}'), ('\u{2F9B6}', '\u{8669}'), ('\u{2F9B7}', '\u{86A9}'), ('\u{2F9B8}', '\u{8688}'),
+    ('\u{2F9B9}', '\u{870E}'), ('\u{2F9BA}', '\u{86E2}'), ('\u{2F9BB}', '\u{8779}'), ('\u{2F9BC}', '\u{8728}'),
+    ('\u{2F9BD}', '\u{876B}'), ('\u{2F9BE}', '\u{8786}'), ('\u{2F9BF}', '\u{45D7}'), ('\u{2F9C0}', '\u{87E1}'),
+    ('\u{2F9C1}', '\u{8801}'), ('\u{2F9C2}', '\u{45F9}'), ('\u{2F9C3}', '\u{8860}'), ('\u{2F9C4}', '\u{8863}'),
+    ('\u{2F9C5}', '\u{27667}'), ('\u{2F9C6}', '\u{88D7}'), ('\u{2F9C7}', '\u{88DE}'), ('\u{2F9C8}', '\u{4635}'),
+    ('\u{2F9C9}', '\u{88FA}'), ('\u{2F9CA}', '\u{34BB}'), ('\u{2F9CB}', '\u{278AE}'), ('\u{2F9CC}', '\u{27966}'),
+    ('\u{2F9CD}', '\u{46BE}'), ('\u{2F9CE}', '\u{46C7}'), ('\u{2F9CF}', '\u{8AA0}'), ('\u{2F9D0}', '\u{8AED}'),
+    ('\u{2F9D1}', '\u{8B8A}'), ('\u{2F9D2}', '\u{8C55}'), ('\u{2F9D3}', '\u{27CA8}'), ('\u{2F9D4}', '\u{8CAB}'),
+    ('\u{2F9D5}', '\u{8CC1}'), ('\u{2F9D6}', '\u{8D1B}'), ('\u{2F9D7}', '\u{8D77}'), ('\u{2F9D8}', '\u{27F2F}'),
+    ('\u{2F9D9}', '\u{20804}'), ('\u{2F9DA}', '\u{8DCB}'), ('\u{2F9DB}', '\u{8DBC}'), ('\u{2F9DC}', '\u{8DF0}'),
+    ('\u{2F9DD}', '\u{208DE}'), ('\u{2F9DE}', '\u{8ED4}'), ('\u{2F9DF}', '\u{8F38}'), ('\u{2F9E0}', '\u{285D2}'),
+    ('\u{2F9E1}', '\u{285ED}'), ('\u{2F9E2}', '\u{9094}'), ('\u{2F9E3}', '\u{90F1}'), ('\u{2F9E4}', '\u{9111}'),
+    ('\u{2F9E5}', '\u{2872E}'), ('\u{2F9E6}', '\u{911B}'), ('\u{2F9E7}', '\u{9238}'), ('\u{2F9E8}', '\u{92D7}'),
+    ('\u{2F9E9}', '\u{92D8}'), ('\u{2F9EA}', '\u{927C}'), ('\u{2F9EB}', '\u{93F9}'), ('\u{2F9EC}', '\u{9415}'),
+    ('\u{2F9ED}', '\u{28BFA}'), ('\u{2F9EE}', '\u{958B}'), ('\u{2F9EF}', '\u{4995}'), ('\u{2F9F0}', '\u{95B7}'),
+    ('\u{2F9F1}', '\u{28D77}'), ('\u{2F9F2}', '\u{49E6}'), ('\u{2F9F3}', '\u{96C3}'), ('\u{2F9F4}', '\u{5DB2}'),
+    ('\u{2F9F5}', '\u{9723}'), ('\u{2F9F6}', '\u{29145}'), ('\u{2F9F7}', '\u{2921A}'), ('\u{2F9F8}', '\u{4A6E}'),
+    ('\u{2F9F9}', '\u{4A76}'), ('\u{2F9FA}', '\u{97E0}'), ('\u{2F9FB}', '\u{2940A}'), ('\u{2F9FC}', '\u{4AB2}'),
+    ('\u{2F9FD}', '\u{29496}'), ('\u{2F9FE}', '\u{980B}'), ('\u{2F9FF}', '\u{980B}'), ('\u{2FA00}', '\u{9829}'),
+    ('\u{2FA01}', '\u{295B6}'), ('\u{2FA02}', '\u{98E2}'), ('\u{2FA03}', '\u{4B33}'), ('\u{2FA04}', '\u{9929}'),
+    ('\u{2FA05}', '\u{99A7}'), ('\u{2FA06}', '\u{99C2}'), ('\u{2FA07}', '\u{99FE}'), ('\u{2FA08}', '\u{4BCE}'),
+    ('\u{2FA09}', '\u{29B30}'), ('\u{2FA0A}', '\u{9B12}'), ('\u{2FA0B}', '\u{9C40}'), ('\u{2FA0C}', '\u{9CFD}'),
+    ('\u{2FA0D}', '\u{4CCE}'), ('\u{2FA0E}', '\u{4CED}'), ('\u{2FA0F}', '\u{9D67}'), ('\u{2FA10}', '\u{2A0CE}'),
+    ('\u{2FA11}', '\u{4CF8}'), ('\u{2FA12}', '\u{2A105}'), ('\u{2FA13}', '\u{2A20E}'), ('\u{2FA14}', '\u{2A291}'),
+    ('\u{2FA15}', '\u{9EBB}'), ('\u{2FA16}', '\u{4D56}'), ('\u{2FA17}', '\u{9EF9}'), ('\u{2FA18}', '\u{9EFE}'),
+    ('\u{2FA19}', '\u{9F05}'), ('\u{2FA1A}', '\u{9F0F}'), ('\u{2FA1B}', '\u{9F16}'), ('\u{2FA1C}', '\u{9F3B}'),
+    ('\u{2FA1D}', '\u{2A600}'),
+];
+
+/// The normalized form of `c`.
+pub fn normalize_char(c: char) -> char {
+    match SINGLETONS.binary_search_by_key(&c, |&(from, _)| from) {
+        Ok(i) => SINGLETONS[i].1,
+        Err(_) => c,
+    }
+}
+
+/// `c` with fullwidth ASCII folded to its halfwidth form (the NFKC mapping
+/// of the fullwidth forms block).
+pub fn fold_width_char(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+/// `s` with fullwidth ASCII folded to halfwidth.
+pub fn fold_width(s: &str) -> String {
+    s.chars().map(fold_width_char).collect()
+}
+
+/// `s` with every character normalized.
+pub fn normalize(s: &str) -> String {
+    s.chars().map(normalize_char).collect()
+}
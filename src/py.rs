@@ -0,0 +1,78 @@
+//! A flat, string-only API for bindings to other languages such as Python.
+//!
+//! Everything here takes and returns plain strings and tuples of them, so
+//! a binding layer (e.g. PyO3 `#[pyfunction]` wrappers) only has to forward
+//! calls: results are `(character, tag)` pairs, which map to Python tuples.
+//! The crate does not depend on PyO3 itself.
+
+use std::path::Path;
+
+use crate::ids::{IDSTable, Tag};
+
+/// An opaque loaded table.
+#[derive(Debug)]
+pub struct Table {
+    inner: IDSTable,
+}
+
+impl Table {
+    pub fn from_string(content: &str) -> Result<Table, String> {
+        IDSTable::load_from_string(content)
+            .map(|inner| Table { inner })
+            .map_err(|e| e.to_string())
+    }
+
+    /// The decomposition of `character` as IDS text, if it has one.
+    pub fn decomposition(&self, character: &str) -> Option<String> {
+        let k = single_char(character).ok()?;
+        self.inner.primary_decomposition(k).map(|ids| ids.to_string())
+    }
+}
+
+pub fn load_table<P: AsRef<Path>>(path: P) -> Result<Table, String> {
+    IDSTable::load_file(path)
+        .map(|inner| Table { inner })
+        .map_err(|e| e.to_string())
+}
+
+pub fn find(table: &Table, needles: &[String]) -> Result<Vec<(String, String)>, String> {
+    crate::search_find(&table.inner, needles).map(flatten)
+}
+
+/// `match` is a keyword in Rust, hence the underscore.
+pub fn match_(table: &Table, pattern: &str) -> Result<Vec<(String, String)>, String> {
+    crate::search_match(&table.inner, pattern).map(flatten)
+}
+
+pub fn pmatch(table: &Table, pattern: &str) -> Result<Vec<(String, String)>, String> {
+    crate::search_pmatch(&table.inner, pattern).map(flatten)
+}
+
+fn flatten(results: Vec<(char, Tag)>) -> Vec<(String, String)> {
+    results.into_iter().map(|(k, tag)| (k.to_string(), tag.to_string())).collect()
+}
+
+fn single_char(s: &str) -> Result<char, String> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(k), None) => Ok(k),
+        _ => Err(format!("Expected a single character, got {:?}", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_are_string_pairs() {
+        let table = Table::from_string("U+6C35 氵 氵\nU+9752 青 青\nU+6E05 清 ⿰氵青[G]\nU+6CB3 河 ⿰氵可\n").unwrap();
+        assert_eq!(find(&table, &["青".to_string()]).unwrap(),
+                   vec![("清".to_string(), "G".to_string()), ("青".to_string(), String::new())]);
+        assert_eq!(match_(&table, "⿰氵.").unwrap().len(), 2);
+        assert_eq!(pmatch(&table, "⿰氵青").unwrap()[0].0, "清");
+        assert_eq!(table.decomposition("清").as_deref(), Some("⿰氵青"));
+        assert_eq!(table.decomposition("清河"), None);
+        assert!(match_(&table, "{").is_err());
+    }
+}
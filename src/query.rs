@@ -0,0 +1,225 @@
+//! Boolean queries over components, such as `(水 AND 青) OR 門`.
+//!
+//! `AND` binds tighter than `OR`, and `NOT` tighter than both; parentheses
+//! group. Everything else up to the next space or parenthesis is a
+//! component, written as for `find`. Keywords are only recognized in upper
+//! case.
+
+use alloc::{boxed::Box, collections::BTreeSet, vec, vec::Vec};
+use core::fmt;
+
+use crate::ids::{parse, IDSTable, ParseError, IDS};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryExpr {
+    /// Characters containing the component in any of their variants
+    Component(IDS),
+    And(Box<QueryExpr>, Box<QueryExpr>),
+    Or(Box<QueryExpr>, Box<QueryExpr>),
+    /// Characters of the table not matched by the inner expression
+    Not(Box<QueryExpr>),
+}
+
+/// Why a query could not be parsed. Positions count characters from the
+/// start of the query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// The query ended where a component or `(` should follow.
+    UnexpectedEnd,
+    /// `)` or a keyword at `position` where a component or `(` should be.
+    UnexpectedToken { position: usize },
+    /// A `(` at `position` without its `)`.
+    Unclosed { position: usize },
+    /// A complete query followed by more input at `position`.
+    TrailingInput { position: usize },
+    /// The component at `position` is not a valid decomposition.
+    Component { position: usize, error: ParseError },
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::UnexpectedEnd => write!(f, "the query ends where a component should follow"),
+            QueryError::UnexpectedToken { position } => write!(f, "expected a component at position {}", position),
+            QueryError::Unclosed { position } => write!(f, "( at position {} is not closed", position),
+            QueryError::TrailingInput { position } => write!(f, "unexpected input at position {}", position),
+            QueryError::Component { position, error } => write!(f, "component at position {}: {}", position, error),
+        }
+    }
+}
+
+impl core::error::Error for QueryError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(&'a str),
+}
+
+// Each token with the position of its first character
+fn tokenize(input: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().enumerate().peekable();
+    while let Some((position, (start, c))) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some((_, (i, c))) = chars.next_if(|(_, (_, c))| !c.is_whitespace() && !"()".contains(*c)) {
+                    end = i + c.len_utf8();
+                }
+                match &input[start..end] {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    word => Token::Word(word),
+                }
+            }
+        };
+        tokens.push((position, token));
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    next: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.next).map(|(_, t)| *t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.next].0
+    }
+
+    fn or(&mut self) -> Result<QueryExpr, QueryError> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(Token::Or) {
+            self.next += 1;
+            expr = QueryExpr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<QueryExpr, QueryError> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(Token::And) {
+            self.next += 1;
+            expr = QueryExpr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<QueryExpr, QueryError> {
+        let Some(token) = self.peek() else {
+            return Err(QueryError::UnexpectedEnd);
+        };
+        let position = self.position();
+        self.next += 1;
+        match token {
+            Token::Not => Ok(QueryExpr::Not(Box::new(self.unary()?))),
+            Token::Open => {
+                let expr = self.or()?;
+                if self.peek() != Some(Token::Close) {
+                    return Err(QueryError::Unclosed { position });
+                }
+                self.next += 1;
+                Ok(expr)
+            }
+            Token::Word(word) => parse(word)
+                .map(QueryExpr::Component)
+                .map_err(|error| QueryError::Component { position, error }),
+            Token::Close | Token::And | Token::Or => Err(QueryError::UnexpectedToken { position }),
+        }
+    }
+}
+
+pub fn parse_query(input: &str) -> Result<QueryExpr, QueryError> {
+    let mut parser = Parser { tokens: tokenize(input), next: 0 };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(_) => Err(QueryError::TrailingInput { position: parser.position() }),
+    }
+}
+
+impl IDSTable {
+    /// The characters matched by `expr`, in code point order. A component
+    /// matches a character if any of its variants contains it.
+    pub fn evaluate(&self, expr: &QueryExpr) -> Vec<char> {
+        self.evaluate_set(expr).into_iter().collect()
+    }
+
+    fn evaluate_set(&self, expr: &QueryExpr) -> BTreeSet<char> {
+        match expr {
+            QueryExpr::Component(IDS::Char(c)) if self.index_applies() => {
+                self.find_leaf_only(*c).into_iter().map(|(k, _)| k).collect()
+            }
+            QueryExpr::Component(needle) => self.iter()
+                .filter(|(_, ids)| self.ids_has_subcomponent(ids, needle))
+                .map(|((k, _), _)| k)
+                .collect(),
+            QueryExpr::And(a, b) => {
+                let a = self.evaluate_set(a);
+                a.intersection(&self.evaluate_set(b)).copied().collect()
+            }
+            QueryExpr::Or(a, b) => {
+                let mut a = self.evaluate_set(a);
+                a.extend(self.evaluate_set(b));
+                a
+            }
+            QueryExpr::Not(a) => {
+                let a = self.evaluate_set(a);
+                self.iter().map(|((k, _), _)| k).filter(|k| !a.contains(k)).collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(s: &str) -> Box<QueryExpr> {
+        Box::new(QueryExpr::Component(parse(s).unwrap()))
+    }
+
+    #[test]
+    fn parses_with_precedence() {
+        assert_eq!(parse_query("(水 AND 青) OR 門"), Ok(QueryExpr::Or(
+            Box::new(QueryExpr::And(component("水"), component("青"))),
+            component("門"),
+        )));
+        assert_eq!(parse_query("水 OR 青 AND NOT 門"), Ok(QueryExpr::Or(
+            component("水"),
+            Box::new(QueryExpr::And(component("青"), Box::new(QueryExpr::Not(component("門"))))),
+        )));
+        assert_eq!(parse_query("⿰氵青AND 水"), Err(QueryError::Component { position: 0, error: ParseError::TrailingInput { position: 3 } }));
+        assert_eq!(parse_query("水 or 青"), Err(QueryError::TrailingInput { position: 2 }));
+        assert_eq!(parse_query("(水 OR 青"), Err(QueryError::Unclosed { position: 0 }));
+        assert_eq!(parse_query("水 AND"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(parse_query("水 OR ) 青"), Err(QueryError::UnexpectedToken { position: 5 }));
+        assert_eq!(parse_query("水 青"), Err(QueryError::TrailingInput { position: 2 }));
+        assert!(matches!(parse_query("水 AND ⿰氵"), Err(QueryError::Component { position: 6, .. })));
+    }
+
+    #[test]
+    fn evaluates_set_operations() {
+        let t = IDSTable::load_from_string("U+6C34 水 水\nU+6C35 氵 水\nU+9752 青 青\nU+9580 門 門\n\
+                                            U+6E05 清 ⿰氵青\nU+6C5F 江 ⿰氵工\nU+5DE5 工 工\nU+95F7 悶 ⿵門心\nU+5FC3 心 心").unwrap();
+        let evaluate = |s| t.evaluate(&parse_query(s).unwrap());
+        assert_eq!(evaluate("(水 AND 青) OR 門"), vec!['悶', '清', '門']);
+        assert_eq!(evaluate("水 AND NOT 青"), vec!['水', '氵', '江']);
+        assert_eq!(evaluate("NOT (水 OR 門)"), vec!['工', '心', '青']);
+        assert_eq!(evaluate("⿰氵青 OR ⿵門心"), vec!['悶', '清']);
+    }
+}